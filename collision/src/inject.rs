@@ -0,0 +1,73 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{Cursor, Seek, Write},
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use level::read_level_header;
+use wad::read_exact_vec;
+
+use crate::{Collision, read_collision, write_collision};
+
+const TRIANGLE_LEN: usize = 12;
+
+/// Copies `triangles` from `collision_file` into the level's collision data,
+/// both have to hold that many triangles
+pub fn replace_triangles(
+    level_file: PathBuf,
+    collision_file: &Path,
+    triangles: Range<u32>,
+) -> anyhow::Result<()> {
+    let triangle_bytes = |collision: &Collision| {
+        collision
+            .sections
+            .iter()
+            .position(|(name, _)| name == "section_8_triangles.bin")
+    };
+    let bytes = triangles.start as usize * TRIANGLE_LEN..triangles.end as usize * TRIANGLE_LEN;
+
+    let replacement = read_collision(&mut File::open(collision_file)?, collision_file)?;
+    let replacement = triangle_bytes(&replacement)
+        .and_then(|index| replacement.sections[index].1.get(bytes.clone()))
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "{} has no triangles {}..{}",
+                collision_file.display(),
+                triangles.start,
+                triangles.end
+            )
+        })?
+        .to_vec();
+
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(&level_file)?;
+    let header = read_level_header(&mut file)?;
+
+    file.seek(std::io::SeekFrom::Start(
+        header.collision_data.offset as u64,
+    ))?;
+    let data = read_exact_vec(&mut file, header.collision_data.length as u64)?;
+
+    let mut collision = read_collision(&mut Cursor::new(data), &level_file)?;
+    triangle_bytes(&collision)
+        .and_then(|index| collision.sections[index].1.get_mut(bytes))
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "{} has no triangles {}..{}",
+                level_file.display(),
+                triangles.start,
+                triangles.end
+            )
+        })?
+        .copy_from_slice(&replacement);
+
+    file.seek(std::io::SeekFrom::Start(
+        header.collision_data.offset as u64,
+    ))?;
+    file.write_all(&write_collision(&collision))?;
+
+    Ok(())
+}
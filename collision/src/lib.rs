@@ -1,10 +1,34 @@
 use std::{
-    fs::{File, create_dir_all},
-    io::{Read, copy},
-    path::PathBuf,
+    fs::{File, create_dir_all, write},
+    io::{self, BufReader, Read, Seek},
+    path::{Path, PathBuf},
 };
 
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use wad::{Migration, read_exact_vec, read_manifest};
+
+pub mod inject;
+
+#[derive(Debug, Error)]
+pub enum CollisionError {
+    #[error("{}", path.display())]
+    Io { path: PathBuf, source: io::Error },
+    #[error("{}: {section} at offset 0x{offset:x}", path.display())]
+    Read {
+        path: PathBuf,
+        section: String,
+        offset: u64,
+        source: io::Error,
+    },
+    #[error("{}: {section} at offset 0x{offset:x}: {reason}", path.display())]
+    Invalid {
+        path: PathBuf,
+        section: String,
+        offset: u64,
+        reason: String,
+    },
+}
 
 // section 0
 pub struct CollisionHeader {
@@ -76,228 +100,255 @@ pub struct CollisionSection14 {
     pub section_15_offset: u32,
 }
 
+pub const SCHEMA_VERSION: u32 = 1;
+
+const MIGRATIONS: [Migration; SCHEMA_VERSION as usize] = [
+    // nothing but the version to add
+    |_, _| Ok(()),
+];
+
 #[derive(Serialize, Deserialize)]
-pub struct CollisionManifest {}
+pub struct CollisionManifest {
+    pub schema_version: u32,
+}
 
-fn copy_limited(file: &mut File, dst_file: &mut File, remaining: u64) -> anyhow::Result<()> {
-    let mut limited = Read::by_ref(file).take(remaining);
+impl CollisionManifest {
+    pub fn load(json_file: &Path) -> anyhow::Result<Self> {
+        read_manifest(json_file, &MIGRATIONS)
+    }
 
-    copy(&mut limited, dst_file)?;
+    pub fn save(&self, json_file: &Path) -> anyhow::Result<()> {
+        serde_json::to_writer_pretty(File::create(json_file)?, self)?;
 
-    Ok(())
+        Ok(())
+    }
 }
 
-pub fn parse_collision(
-    collision_file: PathBuf,
-    output_dir: PathBuf,
-) -> anyhow::Result<CollisionManifest> {
-    let mut file = File::open(&collision_file)?;
-    create_dir_all(&output_dir)?;
+/// A collision file decoded in memory
+pub struct Collision {
+    pub header: CollisionHeader,
+    pub section_1: CollisionSection1,
+    pub section_2: CollisionSection2,
+    pub section_3: CollisionSection3,
+    pub section_5: CollisionSection5,
+    pub collision_types: CollisionTypes,
+    pub section_7: CollisionSection7,
+    pub section_8: CollisionSection8,
+    pub section_9: CollisionSection9,
+    pub section_10: CollisionSection10,
+    pub section_11: CollisionSection11,
+    pub section_12: CollisionSection12,
+    pub section_14: CollisionSection14,
+    /// Raw section data in file order, named after the file Unpack writes
+    /// it to
+    pub sections: Vec<(String, Vec<u8>)>,
+}
 
-    let mut buffer_0 = [0u8; 4];
-    let mut buffer_1 = [0u8; 4];
+struct CollisionReader<R> {
+    file: R,
+    file_len: u64,
+    path: PathBuf,
+    sections: Vec<(String, Vec<u8>)>,
+}
 
-    file.read_exact(&mut buffer_0)?;
-    file.read_exact(&mut buffer_1)?;
+impl<R: Read + Seek> CollisionReader<R> {
+    fn read_error(&mut self, section: &str) -> impl FnOnce(io::Error) -> CollisionError + use<R> {
+        let path = self.path.clone();
+        let section = section.to_string();
+        let offset = self.file.stream_position().unwrap_or_default();
+
+        move |source| CollisionError::Read {
+            path,
+            section,
+            offset,
+            source,
+        }
+    }
 
-    let header = CollisionHeader {
-        section_1_offset: u32::from_le_bytes(buffer_0),
-        section_0_data_len: u32::from_le_bytes(buffer_1),
-    };
+    fn invalid(&mut self, section: &str, reason: String) -> CollisionError {
+        CollisionError::Invalid {
+            path: self.path.clone(),
+            section: section.to_string(),
+            offset: self.file.stream_position().unwrap_or_default(),
+            reason,
+        }
+    }
 
-    let mut section_0_pb = output_dir.clone();
-    section_0_pb.push("section_0.dat");
+    /// Length of a section from the offset of the next one, which counts
+    /// from the start of this section's `header_len` byte header
+    fn section_len(
+        &mut self,
+        section: &str,
+        next_offset: u32,
+        header_len: u64,
+    ) -> Result<u64, CollisionError> {
+        (next_offset as u64).checked_sub(header_len).ok_or_else(|| {
+            self.invalid(
+                section,
+                format!("next section offset 0x{next_offset:x} is inside the 0x{header_len:x} byte header"),
+            )
+        })
+    }
 
-    let mut section_0_file = File::create(section_0_pb)?;
+    fn read_u32(&mut self, section: &str) -> Result<u32, CollisionError> {
+        let error = self.read_error(section);
 
-    copy_limited(
-        &mut file,
-        &mut section_0_file,
-        header.section_1_offset as u64 - 8,
-    )?;
+        let mut buffer = [0u8; 4];
+        self.file.read_exact(&mut buffer).map_err(error)?;
 
-    file.read_exact(&mut buffer_0)?;
-    file.read_exact(&mut buffer_1)?;
+        Ok(u32::from_le_bytes(buffer))
+    }
 
-    let section_1 = CollisionSection1 {
-        section_3_offset: u32::from_le_bytes(buffer_0),
-        section_2_offset: u32::from_le_bytes(buffer_1),
-    };
+    /// Reads the next `length` bytes as section `name`
+    fn read_section(&mut self, name: &str, length: u64) -> Result<(), CollisionError> {
+        let section = name.trim_end_matches(".dat").trim_end_matches(".bin");
+
+        let position = self
+            .file
+            .stream_position()
+            .map_err(self.read_error(section))?;
+        let remaining = self.file_len.saturating_sub(position);
+
+        if length > remaining {
+            return Err(self.invalid(
+                section,
+                format!("0x{length:x} bytes run past the end of the file, 0x{remaining:x} left"),
+            ));
+        }
+
+        let error = self.read_error(section);
+        let data = read_exact_vec(&mut self.file, length).map_err(error)?;
 
-    let section_1_data_len = (section_1.section_2_offset - 4) / 28;
+        self.sections.push((name.to_string(), data));
 
-    let mut section_1_pb = output_dir.clone();
-    section_1_pb.push("section_1.dat");
+        Ok(())
+    }
+}
 
-    let mut section_1_file = File::create(section_1_pb)?;
+/// Decodes a collision file without touching the disk, `collision_file` is
+/// only used in errors
+pub fn read_collision(
+    file: &mut (impl Read + Seek),
+    collision_file: &Path,
+) -> Result<Collision, CollisionError> {
+    let io_error = |source| CollisionError::Io {
+        path: collision_file.to_path_buf(),
+        source,
+    };
 
-    copy_limited(
-        &mut file,
-        &mut section_1_file,
-        section_1_data_len as u64 * 28,
-    )?;
+    let file_len = file.seek(io::SeekFrom::End(0)).map_err(io_error)?;
+    file.rewind().map_err(io_error)?;
 
-    file.read_exact(&mut buffer_0)?;
-    file.read_exact(&mut buffer_1)?;
+    let mut reader = CollisionReader {
+        file,
+        file_len,
+        path: collision_file.to_path_buf(),
+        sections: Vec::new(),
+    };
 
-    let section_2 = CollisionSection2 {
-        section_2_data_len: u32::from_le_bytes(buffer_0),
-        section_4_offset: u32::from_le_bytes(buffer_1),
+    let header = CollisionHeader {
+        section_1_offset: reader.read_u32("header")?,
+        section_0_data_len: reader.read_u32("header")?,
     };
 
-    let mut section_2_offsets_pb = output_dir.clone();
-    section_2_offsets_pb.push("section_2_offsets.dat");
+    let section_0_len = reader.section_len("section_0", header.section_1_offset, 8)?;
+    reader.read_section("section_0.dat", section_0_len)?;
 
-    let mut section_2_offsets_file = File::create(section_2_offsets_pb)?;
+    let section_1 = CollisionSection1 {
+        section_3_offset: reader.read_u32("section_1")?,
+        section_2_offset: reader.read_u32("section_1")?,
+    };
 
-    copy_limited(
-        &mut file,
-        &mut section_2_offsets_file,
-        section_2.section_2_data_len as u64 * 4,
-    )?;
+    let section_1_data_len = reader.section_len("section_1", section_1.section_2_offset, 4)? / 28;
 
-    let section_2_data_len = section_1.section_3_offset
-        - 8
-        - section_1_data_len * 28
-        - 8
-        - section_2.section_2_data_len * 4;
+    reader.read_section("section_1.dat", section_1_data_len * 28)?;
 
-    let mut section_2_pb = output_dir.clone();
-    section_2_pb.push("section_2.dat");
+    let section_2 = CollisionSection2 {
+        section_2_data_len: reader.read_u32("section_2")?,
+        section_4_offset: reader.read_u32("section_2")?,
+    };
 
-    let mut section_2_file = File::create(section_2_pb)?;
+    reader.read_section(
+        "section_2_offsets.dat",
+        section_2.section_2_data_len as u64 * 4,
+    )?;
 
-    copy_limited(&mut file, &mut section_2_file, section_2_data_len as u64)?;
+    // section 3 offset counts from the start of section 1
+    let section_2_start = 8 + section_1_data_len * 28 + 8 + section_2.section_2_data_len as u64 * 4;
+    let section_2_data_len =
+        reader.section_len("section_2", section_1.section_3_offset, section_2_start)?;
 
-    file.read_exact(&mut buffer_0)?;
+    reader.read_section("section_2.dat", section_2_data_len)?;
 
     let section_3 = CollisionSection3 {
-        section_5_offset: u32::from_le_bytes(buffer_0),
+        section_5_offset: reader.read_u32("section_3")?,
     };
 
-    let mut section_3_pb = output_dir.clone();
-    section_3_pb.push("section_3.dat");
-
-    let mut section_3_file = File::create(section_3_pb)?;
-
-    copy_limited(
-        &mut file,
-        &mut section_3_file,
-        section_3.section_5_offset as u64 - 4,
-    )?;
-
-    file.read_exact(&mut buffer_0)?;
+    let section_3_len = reader.section_len("section_3", section_3.section_5_offset, 4)?;
+    reader.read_section("section_3.dat", section_3_len)?;
 
     let section_5 = CollisionSection5 {
-        collision_types_offset: u32::from_le_bytes(buffer_0),
+        collision_types_offset: reader.read_u32("section_5")?,
     };
 
-    let mut section_5_pb = output_dir.clone();
-    section_5_pb.push("section_5.dat");
-
-    let mut section_5_file = File::create(section_5_pb)?;
-
-    copy_limited(
-        &mut file,
-        &mut section_5_file,
-        section_5.collision_types_offset as u64 - 4,
-    )?;
-
-    file.read_exact(&mut buffer_0)?;
-    file.read_exact(&mut buffer_1)?;
+    let section_5_len = reader.section_len("section_5", section_5.collision_types_offset, 4)?;
+    reader.read_section("section_5.dat", section_5_len)?;
 
     let collision_types = CollisionTypes {
-        section_7_offset: u32::from_le_bytes(buffer_0),
-        collision_types_len: u32::from_le_bytes(buffer_1),
+        section_7_offset: reader.read_u32("collision_types")?,
+        collision_types_len: reader.read_u32("collision_types")?,
     };
 
-    let mut collision_types_pb = output_dir.clone();
-    collision_types_pb.push("collision_types.dat");
-
-    let mut collision_types_file = File::create(collision_types_pb)?;
-
-    copy_limited(
-        &mut file,
-        &mut collision_types_file,
-        collision_types.section_7_offset as u64 - 8,
-    )?;
-
-    file.read_exact(&mut buffer_0)?;
+    let collision_types_len =
+        reader.section_len("collision_types", collision_types.section_7_offset, 8)?;
+    reader.read_section("collision_types.dat", collision_types_len)?;
 
     let section_7 = CollisionSection7 {
-        section_8_offset: u32::from_le_bytes(buffer_0),
+        section_8_offset: reader.read_u32("section_7")?,
     };
 
-    let mut section_7_pb = output_dir.clone();
-    section_7_pb.push("section_7.dat");
-
-    let mut section_7_file = File::create(section_7_pb)?;
-
-    copy_limited(
-        &mut file,
-        &mut section_7_file,
-        section_7.section_8_offset as u64 - 4,
-    )?;
-
-    let mut vec_3_pb = output_dir.clone();
-    vec_3_pb.push("vec_3.dat");
-
-    let mut vec_3_file = File::create(vec_3_pb)?;
-
-    copy_limited(&mut file, &mut vec_3_file, 12)?;
-
-    file.read_exact(&mut buffer_0)?;
-    file.read_exact(&mut buffer_1)?;
-
-    let section_9_offset = u32::from_le_bytes(buffer_0);
-    let triangle_count = u32::from_le_bytes(buffer_1);
-
-    file.read_exact(&mut buffer_0)?;
-    file.read_exact(&mut buffer_1)?;
-
-    let idfk_offset = u32::from_le_bytes(buffer_0);
-    let unk_0 = u32::from_le_bytes(buffer_1);
-
-    file.read_exact(&mut buffer_0)?;
-    file.read_exact(&mut buffer_1)?;
-
-    let unk_1_offset = u32::from_le_bytes(buffer_0);
-    let unk_2_offset = u32::from_le_bytes(buffer_1);
+    let section_7_len = reader.section_len("section_7", section_7.section_8_offset, 4)?;
+    reader.read_section("section_7.dat", section_7_len)?;
 
-    file.read_exact(&mut buffer_0)?;
-    file.read_exact(&mut buffer_1)?;
-
-    let triangles_offset = u32::from_le_bytes(buffer_0);
-    let collision_flags_offset = u32::from_le_bytes(buffer_1);
-
-    file.read_exact(&mut buffer_0)?;
-    file.read_exact(&mut buffer_1)?;
+    reader.read_section("vec_3.dat", 12)?;
 
     let section_8 = CollisionSection8 {
-        section_9_offset,
-        triangle_count,
-        idfk_offset,
-        unk_0,
-        unk_1_offset,
-        unk_2_offset,
-        triangles_offset,
-        collision_flags_offset,
-        unk_3_offset: u32::from_le_bytes(buffer_0),
-        unk_4_offset: u32::from_le_bytes(buffer_1),
+        section_9_offset: reader.read_u32("section_8")?,
+        triangle_count: reader.read_u32("section_8")?,
+        idfk_offset: reader.read_u32("section_8")?,
+        unk_0: reader.read_u32("section_8")?,
+        unk_1_offset: reader.read_u32("section_8")?,
+        unk_2_offset: reader.read_u32("section_8")?,
+        triangles_offset: reader.read_u32("section_8")?,
+        collision_flags_offset: reader.read_u32("section_8")?,
+        unk_3_offset: reader.read_u32("section_8")?,
+        unk_4_offset: reader.read_u32("section_8")?,
     };
 
+    let section_9_start = reader.section_len("section_8", section_8.section_9_offset, 4)? as u32;
+
     let mut files = [
-        ("section_8_idfk.bin", idfk_offset),
-        ("section_8_unk_0.bin", unk_0),
-        ("section_8_unk_1.bin", unk_1_offset),
-        ("section_8_unk_2.bin", unk_2_offset),
-        ("section_8_triangles.bin", triangles_offset),
-        ("section_8_collision_flags.bin", collision_flags_offset),
+        ("section_8_idfk.bin", section_8.idfk_offset),
+        ("section_8_unk_0.bin", section_8.unk_0),
+        ("section_8_unk_1.bin", section_8.unk_1_offset),
+        ("section_8_unk_2.bin", section_8.unk_2_offset),
+        ("section_8_triangles.bin", section_8.triangles_offset),
+        (
+            "section_8_collision_flags.bin",
+            section_8.collision_flags_offset,
+        ),
         ("section_8_unk_3.bin", section_8.unk_3_offset),
         ("section_8_unk_4.bin", section_8.unk_4_offset),
-        ("", section_9_offset - 4),
+        ("", section_9_start),
     ];
-    files.sort_by(|(_, a), (_, b)| a.cmp(b));
+    files.sort_by_key(|(_, offset)| *offset);
+
+    if files.last().is_some_and(|(name, _)| !name.is_empty()) {
+        return Err(reader.invalid(
+            "section_8",
+            format!("a table offset is past the start of section 9 at 0x{section_9_start:x}"),
+        ));
+    }
 
     for x in files.windows(2) {
         let f1 = x[0];
@@ -307,119 +358,176 @@ pub fn parse_collision(
             continue;
         }
 
-        let mut ff_pb = output_dir.clone();
-        ff_pb.push(f1.0);
-
-        let mut ff_file = File::create(ff_pb)?;
-
-        copy_limited(&mut file, &mut ff_file, (f2.1 - f1.1) as u64)?;
+        reader.read_section(f1.0, (f2.1 - f1.1) as u64)?;
     }
 
-    file.read_exact(&mut buffer_0)?;
-
     let section_9 = CollisionSection9 {
-        section_10_offset: u32::from_le_bytes(buffer_0),
+        section_10_offset: reader.read_u32("section_9")?,
     };
 
-    let mut section_9_pb = output_dir.clone();
-    section_9_pb.push("section_9.dat");
-
-    let mut section_9_file = File::create(section_9_pb)?;
-
-    copy_limited(
-        &mut file,
-        &mut section_9_file,
-        section_9.section_10_offset as u64 - 4,
-    )?;
-
-    file.read_exact(&mut buffer_0)?;
-    file.read_exact(&mut buffer_1)?;
+    let section_9_len = reader.section_len("section_9", section_9.section_10_offset, 4)?;
+    reader.read_section("section_9.dat", section_9_len)?;
 
     let section_10 = CollisionSection10 {
-        section_11_offset: u32::from_le_bytes(buffer_0),
-        section_10_data_len: u32::from_le_bytes(buffer_1),
+        section_11_offset: reader.read_u32("section_10")?,
+        section_10_data_len: reader.read_u32("section_10")?,
     };
 
-    let mut section_10_pb = output_dir.clone();
-    section_10_pb.push("section_10.dat");
-
-    let mut section_10_file = File::create(section_10_pb)?;
-
-    copy_limited(
-        &mut file,
-        &mut section_10_file,
-        section_10.section_11_offset as u64 - 8,
-    )?;
-
-    file.read_exact(&mut buffer_0)?;
-    file.read_exact(&mut buffer_1)?;
+    let section_10_len = reader.section_len("section_10", section_10.section_11_offset, 8)?;
+    reader.read_section("section_10.dat", section_10_len)?;
 
     let section_11 = CollisionSection11 {
-        section_12_offset: u32::from_le_bytes(buffer_0),
-        section_11_data_len: u32::from_le_bytes(buffer_1),
+        section_12_offset: reader.read_u32("section_11")?,
+        section_11_data_len: reader.read_u32("section_11")?,
     };
 
-    let mut section_11_pb = output_dir.clone();
-    section_11_pb.push("section_11.dat");
-
-    let mut section_11_file = File::create(section_11_pb)?;
-
-    copy_limited(
-        &mut file,
-        &mut section_11_file,
-        section_11.section_12_offset as u64 - 8,
-    )?;
-
-    file.read_exact(&mut buffer_0)?;
-    file.read_exact(&mut buffer_1)?;
+    let section_11_len = reader.section_len("section_11", section_11.section_12_offset, 8)?;
+    reader.read_section("section_11.dat", section_11_len)?;
 
     let section_12 = CollisionSection12 {
-        section_13_offset: u32::from_le_bytes(buffer_0),
-        section_12_data_len: u32::from_le_bytes(buffer_1),
+        section_13_offset: reader.read_u32("section_12")?,
+        section_12_data_len: reader.read_u32("section_12")?,
     };
 
-    let mut section_12_pb = output_dir.clone();
-    section_12_pb.push("section_12.dat");
-
-    let mut section_12_file = File::create(section_12_pb)?;
-
-    copy_limited(
-        &mut file,
-        &mut section_12_file,
-        section_12.section_13_offset as u64 - 8,
-    )?;
+    let section_12_len = reader.section_len("section_12", section_12.section_13_offset, 8)?;
+    reader.read_section("section_12.dat", section_12_len)?;
 
-    let mut section_13_pb = output_dir.clone();
-    section_13_pb.push("section_13.dat");
+    reader.read_section("section_13.dat", 32)?;
 
-    let mut section_13_file = File::create(section_13_pb)?;
+    let section_14 = CollisionSection14 {
+        section_15_offset: reader.read_u32("section_14")?,
+    };
 
-    copy_limited(&mut file, &mut section_13_file, 32)?;
+    let section_14_len = reader.section_len("section_14", section_14.section_15_offset, 4)?;
+    reader.read_section("section_14.dat", section_14_len)?;
+
+    // read to end
+    let mut tail = Vec::new();
+    let error = reader.read_error("tail");
+    reader.file.read_to_end(&mut tail).map_err(error)?;
+    reader.sections.push(("tail.bin".into(), tail));
+
+    Ok(Collision {
+        header,
+        section_1,
+        section_2,
+        section_3,
+        section_5,
+        collision_types,
+        section_7,
+        section_8,
+        section_9,
+        section_10,
+        section_11,
+        section_12,
+        section_14,
+        sections: reader.sections,
+    })
+}
 
-    file.read_exact(&mut buffer_0)?;
+/// Inverse of read_collision, puts the section headers back in front of
+/// the sections they were read with
+pub fn write_collision(collision: &Collision) -> Vec<u8> {
+    let section_8 = &collision.section_8;
+
+    let mut bytes = Vec::new();
+
+    for (name, data) in collision.sections.iter() {
+        let header: &[u32] = match name.as_str() {
+            "section_0.dat" => &[
+                collision.header.section_1_offset,
+                collision.header.section_0_data_len,
+            ],
+            "section_1.dat" => &[
+                collision.section_1.section_3_offset,
+                collision.section_1.section_2_offset,
+            ],
+            "section_2_offsets.dat" => &[
+                collision.section_2.section_2_data_len,
+                collision.section_2.section_4_offset,
+            ],
+            "section_3.dat" => &[collision.section_3.section_5_offset],
+            "section_5.dat" => &[collision.section_5.collision_types_offset],
+            "collision_types.dat" => &[
+                collision.collision_types.section_7_offset,
+                collision.collision_types.collision_types_len,
+            ],
+            "section_7.dat" => &[collision.section_7.section_8_offset],
+            "section_9.dat" => &[collision.section_9.section_10_offset],
+            "section_10.dat" => &[
+                collision.section_10.section_11_offset,
+                collision.section_10.section_10_data_len,
+            ],
+            "section_11.dat" => &[
+                collision.section_11.section_12_offset,
+                collision.section_11.section_11_data_len,
+            ],
+            "section_12.dat" => &[
+                collision.section_12.section_13_offset,
+                collision.section_12.section_12_data_len,
+            ],
+            "section_14.dat" => &[collision.section_14.section_15_offset],
+            _ => &[],
+        };
+
+        header
+            .iter()
+            .for_each(|value| bytes.extend(value.to_le_bytes()));
+        bytes.extend(data);
+
+        // section 8's table follows vec_3, its files come after it
+        if name == "vec_3.dat" {
+            for value in [
+                section_8.section_9_offset,
+                section_8.triangle_count,
+                section_8.idfk_offset,
+                section_8.unk_0,
+                section_8.unk_1_offset,
+                section_8.unk_2_offset,
+                section_8.triangles_offset,
+                section_8.collision_flags_offset,
+                section_8.unk_3_offset,
+                section_8.unk_4_offset,
+            ] {
+                bytes.extend(value.to_le_bytes());
+            }
+        }
+    }
 
-    let section_14 = CollisionSection14 {
-        section_15_offset: u32::from_le_bytes(buffer_0),
-    };
+    bytes
+}
 
-    let mut section_14_pb = output_dir.clone();
-    section_14_pb.push("section_14.dat");
+/// Writes every section of `collision` into its own file in `output_dir`
+pub fn extract_collision(
+    collision: &Collision,
+    output_dir: &Path,
+) -> Result<CollisionManifest, CollisionError> {
+    create_dir_all(output_dir).map_err(|source| CollisionError::Io {
+        path: output_dir.to_path_buf(),
+        source,
+    })?;
 
-    let mut section_14_file = File::create(section_14_pb)?;
+    for (name, data) in collision.sections.iter() {
+        let dst = output_dir.join(name);
 
-    copy_limited(
-        &mut file,
-        &mut section_14_file,
-        section_14.section_15_offset as u64 - 4,
-    )?;
+        write(&dst, data).map_err(|source| CollisionError::Io { path: dst, source })?;
+    }
 
-    let mut tail = output_dir.clone();
-    tail.push("tail.bin");
+    Ok(CollisionManifest {
+        schema_version: SCHEMA_VERSION,
+    })
+}
 
-    let mut tail_file = File::create(tail)?;
+pub fn parse_collision(
+    collision_file: PathBuf,
+    output_dir: PathBuf,
+) -> Result<CollisionManifest, CollisionError> {
+    let file = File::open(&collision_file).map_err(|source| CollisionError::Io {
+        path: collision_file.clone(),
+        source,
+    })?;
 
-    // copy to end
-    copy(&mut file, &mut tail_file)?;
+    let collision = read_collision(&mut BufReader::new(file), &collision_file)?;
 
-    Ok(CollisionManifest {})
+    extract_collision(&collision, &output_dir)
 }
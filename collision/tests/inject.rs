@@ -0,0 +1,81 @@
+use std::{fs, io::Cursor, path::Path};
+
+use collision::{inject::replace_triangles, read_collision, write_collision};
+use proptest::prelude::*;
+
+const TRIANGLES: &str = "section_8_triangles.bin";
+
+fn triangle_bytes(bytes: &[u8]) -> Vec<u8> {
+    read_collision(&mut Cursor::new(bytes), Path::new("collision_data.bin"))
+        .unwrap()
+        .sections
+        .into_iter()
+        .find(|(name, _)| name == TRIANGLES)
+        .unwrap()
+        .1
+}
+
+/// A level header with only the collision entry set, the collision data
+/// right after it
+fn level(collision: &[u8]) -> Vec<u8> {
+    let mut bytes = vec![0u8; 448];
+    bytes[8..12].copy_from_slice(&448u32.to_le_bytes());
+    bytes[12..16].copy_from_slice(&(collision.len() as u32).to_le_bytes());
+    bytes.extend(collision);
+
+    bytes
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(32))]
+
+    #[test]
+    fn replace_triangles_only_writes_the_range(
+        bytes in fixtures::collision::collision(),
+        split: prop::sample::Index,
+    ) {
+        let dir = tempfile::tempdir().unwrap();
+        let level_file = dir.path().join("level.dat");
+        fs::write(&level_file, level(&bytes)).unwrap();
+
+        let mut replacement =
+            read_collision(&mut Cursor::new(&bytes), Path::new("collision_data.bin")).unwrap();
+        let section = replacement
+            .sections
+            .iter_mut()
+            .find(|(name, _)| name == TRIANGLES)
+            .unwrap();
+        section.1.iter_mut().for_each(|byte| *byte = !*byte);
+
+        let collision_file = dir.path().join("collision_data.bin");
+        fs::write(&collision_file, write_collision(&replacement)).unwrap();
+
+        let count = triangle_bytes(&bytes).len() / 12;
+        let end = split.index(count + 1);
+
+        replace_triangles(level_file.clone(), &collision_file, 0..end as u32).unwrap();
+
+        let after = fs::read(&level_file).unwrap();
+        let before = triangle_bytes(&bytes);
+        let mut expected = before.clone();
+        expected[..end * 12].iter_mut().for_each(|byte| *byte = !*byte);
+
+        prop_assert_eq!(after.len(), 448 + bytes.len());
+        prop_assert_eq!(triangle_bytes(&after[448..]), expected);
+    }
+
+    #[test]
+    fn replace_triangles_refuses_missing_triangles(bytes in fixtures::collision::collision()) {
+        let dir = tempfile::tempdir().unwrap();
+        let level_file = dir.path().join("level.dat");
+        fs::write(&level_file, level(&bytes)).unwrap();
+
+        let collision_file = dir.path().join("collision_data.bin");
+        fs::write(&collision_file, &bytes).unwrap();
+
+        let count = triangle_bytes(&bytes).len() as u32 / 12;
+
+        prop_assert!(replace_triangles(level_file.clone(), &collision_file, 0..count + 1).is_err());
+        prop_assert_eq!(fs::read(&level_file).unwrap(), level(&bytes));
+    }
+}
@@ -0,0 +1,46 @@
+use std::{fs, io::Cursor, path::Path};
+
+use collision::{parse_collision, read_collision, write_collision};
+use proptest::prelude::*;
+
+proptest! {
+    #[test]
+    fn read_then_write_is_identical(bytes in fixtures::collision::collision()) {
+        let collision =
+            read_collision(&mut Cursor::new(&bytes), Path::new("collision_data.bin")).unwrap();
+
+        prop_assert_eq!(write_collision(&collision), bytes);
+    }
+
+    #[test]
+    fn parse_output_rebuilds_the_file(bytes in fixtures::collision::collision()) {
+        let dir = tempfile::tempdir().unwrap();
+        let collision_file = dir.path().join("collision_data.bin");
+        let output_dir = dir.path().join("collision");
+        fs::write(&collision_file, &bytes).unwrap();
+
+        parse_collision(collision_file, output_dir.clone()).unwrap();
+
+        let mut collision =
+            read_collision(&mut Cursor::new(&bytes), Path::new("collision_data.bin")).unwrap();
+
+        for (name, data) in collision.sections.iter_mut() {
+            *data = fs::read(output_dir.join(name)).unwrap();
+        }
+
+        prop_assert_eq!(write_collision(&collision), bytes);
+    }
+
+    #[test]
+    fn truncated_files_never_panic(bytes in fixtures::collision::collision(), cut in any::<prop::sample::Index>()) {
+        let truncated = &bytes[..cut.index(bytes.len())];
+
+        // cuts inside the tail still parse, anything earlier must be an
+        // error rather than a panic
+        if let Ok(collision) =
+            read_collision(&mut Cursor::new(truncated), Path::new("collision_data.bin"))
+        {
+            prop_assert_eq!(write_collision(&collision), truncated);
+        }
+    }
+}
@@ -0,0 +1,246 @@
+use std::{
+    fs::{self, File},
+    io::Write,
+    path::PathBuf,
+};
+
+pub const EXE_HEADER_LEN: usize = 0x800;
+
+const MAGIC: &[u8; 8] = b"PS-X EXE";
+
+/// The BIOS loads the text segment in whole CD sectors
+const SECTOR_SIZE: u32 = 2048;
+
+/// Main RAM, mirrored in every segment
+const RAM_SIZE: u32 = 0x20_0000;
+
+#[derive(Copy, Clone, Debug)]
+pub struct ExeHeader {
+    /// Entry point
+    pub pc0: u32,
+    pub gp0: u32,
+    /// RAM address the text segment is loaded at
+    pub t_addr: u32,
+    pub t_size: u32,
+    pub d_addr: u32,
+    pub d_size: u32,
+    pub b_addr: u32,
+    pub b_size: u32,
+    /// Initial stack pointer, 0 keeps the BIOS default
+    pub s_addr: u32,
+    pub s_size: u32,
+}
+
+pub struct Exe {
+    pub header: ExeHeader,
+    /// Full 2 KiB header, kept so the region string and padding survive
+    pub raw_header: Vec<u8>,
+    pub text: Vec<u8>,
+    /// Whatever follows the text segment in the file, the BIOS doesn't load
+    /// it but save keeps it
+    pub trailing: Vec<u8>,
+}
+
+fn u32_at(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([
+        data[offset],
+        data[offset + 1],
+        data[offset + 2],
+        data[offset + 3],
+    ])
+}
+
+impl Exe {
+    pub fn load(exe_file: PathBuf) -> anyhow::Result<Self> {
+        let data = fs::read(&exe_file)?;
+
+        if data.len() < EXE_HEADER_LEN || &data[0..8] != MAGIC {
+            anyhow::bail!("{} is not a PS-X EXE", exe_file.display());
+        }
+
+        let header = ExeHeader {
+            pc0: u32_at(&data, 0x10),
+            gp0: u32_at(&data, 0x14),
+            t_addr: u32_at(&data, 0x18),
+            t_size: u32_at(&data, 0x1c),
+            d_addr: u32_at(&data, 0x20),
+            d_size: u32_at(&data, 0x24),
+            b_addr: u32_at(&data, 0x28),
+            b_size: u32_at(&data, 0x2c),
+            s_addr: u32_at(&data, 0x30),
+            s_size: u32_at(&data, 0x34),
+        };
+
+        if header.t_addr.checked_add(header.t_size).is_none() {
+            anyhow::bail!(
+                "{}: text segment 0x{:08x} + 0x{:x} runs past the end of the address space",
+                exe_file.display(),
+                header.t_addr,
+                header.t_size
+            );
+        }
+
+        let text_end = EXE_HEADER_LEN + header.t_size as usize;
+        if data.len() < text_end {
+            anyhow::bail!(
+                "{}: t_size is 0x{:x} but the file only has 0x{:x} bytes of text",
+                exe_file.display(),
+                header.t_size,
+                data.len() - EXE_HEADER_LEN
+            );
+        }
+
+        Ok(Self {
+            header,
+            raw_header: data[..EXE_HEADER_LEN].to_vec(),
+            text: data[EXE_HEADER_LEN..text_end].to_vec(),
+            trailing: data[text_end..].to_vec(),
+        })
+    }
+
+    pub fn save(&self, exe_file: PathBuf) -> anyhow::Result<()> {
+        let mut raw_header = self.raw_header.clone();
+
+        let fields = [
+            (0x10, self.header.pc0),
+            (0x14, self.header.gp0),
+            (0x18, self.header.t_addr),
+            (0x1c, self.header.t_size),
+            (0x20, self.header.d_addr),
+            (0x24, self.header.d_size),
+            (0x28, self.header.b_addr),
+            (0x2c, self.header.b_size),
+            (0x30, self.header.s_addr),
+            (0x34, self.header.s_size),
+        ];
+
+        for (offset, value) in fields {
+            raw_header[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+        }
+
+        let mut dst_file = File::create(exe_file)?;
+
+        dst_file.write_all(&raw_header)?;
+        dst_file.write_all(&self.text)?;
+        dst_file.write_all(&self.trailing)?;
+
+        Ok(())
+    }
+
+    /// None when the header's text segment wraps around the address space
+    pub fn text_end(&self) -> Option<u32> {
+        self.header.t_addr.checked_add(self.header.t_size)
+    }
+
+    pub fn contains(&self, address: u32) -> bool {
+        address >= self.header.t_addr && self.text_end().is_some_and(|end| address < end)
+    }
+
+    /// File offset of a RAM address inside the text segment
+    pub fn file_offset(&self, address: u32) -> Option<u64> {
+        if !self.contains(address) {
+            return None;
+        }
+
+        Some((address - self.header.t_addr) as u64 + EXE_HEADER_LEN as u64)
+    }
+
+    /// RAM address of a file offset inside the text segment
+    pub fn address(&self, file_offset: u64) -> Option<u32> {
+        let text_offset = file_offset.checked_sub(EXE_HEADER_LEN as u64)?;
+
+        if text_offset >= self.header.t_size as u64 {
+            return None;
+        }
+
+        Some(self.header.t_addr + text_offset as u32)
+    }
+
+    fn text_range(&self, address: u32, len: usize) -> anyhow::Result<std::ops::Range<usize>> {
+        let start = address.wrapping_sub(self.header.t_addr) as usize;
+
+        if !self.contains(address) || start + len > self.text.len() {
+            anyhow::bail!(
+                "0x{address:08x}..0x{:08x} is outside the text segment (0x{:08x} + 0x{:x})",
+                address as u64 + len as u64,
+                self.header.t_addr,
+                self.header.t_size
+            );
+        }
+
+        Ok(start..start + len)
+    }
+
+    pub fn read(&self, address: u32, len: usize) -> anyhow::Result<&[u8]> {
+        let range = self.text_range(address, len)?;
+
+        Ok(&self.text[range])
+    }
+
+    pub fn write(&mut self, address: u32, data: &[u8]) -> anyhow::Result<()> {
+        let range = self.text_range(address, data.len())?;
+
+        self.text[range].copy_from_slice(data);
+
+        Ok(())
+    }
+
+    pub fn read_u32(&self, address: u32) -> anyhow::Result<u32> {
+        Ok(u32_at(self.read(address, 4)?, 0))
+    }
+
+    pub fn write_u32(&mut self, address: u32, value: u32) -> anyhow::Result<()> {
+        self.write(address, &value.to_le_bytes())
+    }
+
+    /// Appends at least `extra` zeroed bytes to the text segment for a code
+    /// cave, rounded up to a whole sector. A sized bss right after the text
+    /// is zeros either way, the text grows over it and the cave goes past
+    /// it. Returns the RAM address of the cave.
+    pub fn grow(&mut self, extra: u32) -> anyhow::Result<u32> {
+        let too_large = || anyhow::anyhow!("can't grow the text segment by 0x{extra:x} bytes");
+
+        let text_end = self.text_end().ok_or_else(too_large)?;
+
+        let bss_follows = self.header.b_addr == text_end && self.header.b_size != 0;
+        let skip = if bss_follows { self.header.b_size } else { 0 };
+
+        let cave = text_end.checked_add(skip).ok_or_else(too_large)?;
+
+        let grown = skip
+            .checked_add(extra)
+            .ok_or_else(too_large)?
+            .div_ceil(SECTOR_SIZE)
+            .checked_mul(SECTOR_SIZE)
+            .ok_or_else(too_large)?;
+        let new_end = text_end.checked_add(grown).ok_or_else(too_large)?;
+        let t_size = self
+            .header
+            .t_size
+            .checked_add(grown)
+            .ok_or_else(too_large)?;
+
+        // the segment is addressed through KSEG0, KSEG1 or KUSEG
+        if (text_end & 0x1fff_ffff) as u64 + grown as u64 > RAM_SIZE as u64 {
+            anyhow::bail!("growing the text segment to 0x{new_end:08x} runs past the end of RAM");
+        }
+
+        // the cave must not run into the zero filled bss, the game may size
+        // it itself and leave b_size at 0
+        if !bss_follows
+            && self.header.b_addr != 0
+            && text_end <= self.header.b_addr
+            && new_end > self.header.b_addr
+        {
+            anyhow::bail!(
+                "growing the text segment to 0x{new_end:08x} overlaps bss at 0x{:08x}",
+                self.header.b_addr
+            );
+        }
+
+        self.text.resize(self.text.len() + grown as usize, 0);
+        self.header.t_size = t_size;
+
+        Ok(cave)
+    }
+}
@@ -0,0 +1,63 @@
+use exe::{EXE_HEADER_LEN, Exe, ExeHeader};
+
+fn exe(t_addr: u32, t_size: u32, b_addr: u32, b_size: u32) -> Exe {
+    Exe {
+        header: ExeHeader {
+            pc0: t_addr,
+            gp0: 0,
+            t_addr,
+            t_size,
+            d_addr: 0,
+            d_size: 0,
+            b_addr,
+            b_size,
+            s_addr: 0,
+            s_size: 0,
+        },
+        raw_header: vec![0; EXE_HEADER_LEN],
+        text: vec![0; t_size as usize],
+        trailing: Vec::new(),
+    }
+}
+
+#[test]
+fn grows_by_whole_sectors() {
+    let mut exe = exe(0x8001_0000, 0x800, 0, 0);
+
+    assert_eq!(exe.grow(1).unwrap(), 0x8001_0800);
+    assert_eq!(exe.header.t_size, 0x1000);
+    assert_eq!(exe.text.len(), 0x1000);
+}
+
+#[test]
+fn rejects_sizes_that_overflow() {
+    let mut exe = exe(0x8001_0000, 0x800, 0, 0);
+
+    assert!(exe.grow(u32::MAX).is_err());
+    assert_eq!(exe.header.t_size, 0x800);
+}
+
+#[test]
+fn stops_at_the_end_of_ram() {
+    let mut exe = exe(0x801f_f000, 0x800, 0, 0);
+
+    assert!(exe.grow(0x800).is_ok());
+    assert!(exe.grow(0x800).is_err());
+}
+
+#[test]
+fn stops_at_bss_without_a_size() {
+    let mut exe = exe(0x8001_0000, 0x800, 0x8001_1000, 0);
+
+    assert!(exe.grow(0x800).is_ok());
+    assert!(exe.grow(0x800).is_err());
+}
+
+#[test]
+fn grows_past_bss_that_follows_text() {
+    let mut exe = exe(0x8001_0000, 0x800, 0x8001_0800, 0x400);
+
+    assert_eq!(exe.grow(0x100).unwrap(), 0x8001_0c00);
+    assert_eq!(exe.header.t_size, 0x1000);
+    assert_eq!(exe.text.len(), 0x1000);
+}
@@ -0,0 +1,81 @@
+use std::fs;
+
+use exe::{EXE_HEADER_LEN, Exe};
+
+/// A PS-X EXE with `t_size` bytes of text at `t_addr` and `trailing` after it
+fn exe_file(t_addr: u32, t_size: u32, trailing: &[u8]) -> Vec<u8> {
+    let mut bytes = vec![0u8; EXE_HEADER_LEN];
+    bytes[..8].copy_from_slice(b"PS-X EXE");
+    bytes[0x10..0x14].copy_from_slice(&t_addr.to_le_bytes());
+    bytes[0x18..0x1c].copy_from_slice(&t_addr.to_le_bytes());
+    bytes[0x1c..0x20].copy_from_slice(&t_size.to_le_bytes());
+    // region string, only kept through raw_header
+    bytes[0x4c..0x5a].copy_from_slice(b"Sony Computer ");
+
+    bytes.extend((0..t_size).map(|i| i as u8));
+    bytes.extend(trailing);
+
+    bytes
+}
+
+#[test]
+fn load_then_save_is_identical() {
+    let dir = tempfile::tempdir().unwrap();
+    let src = dir.path().join("SCUS_944.25");
+    let dst = dir.path().join("saved");
+
+    let bytes = exe_file(0x8001_0000, 0x1000, b"trailing data");
+    fs::write(&src, &bytes).unwrap();
+
+    let exe = Exe::load(src).unwrap();
+    assert_eq!(exe.text.len(), 0x1000);
+    assert_eq!(exe.trailing, b"trailing data");
+
+    exe.save(dst.clone()).unwrap();
+
+    assert_eq!(fs::read(dst).unwrap(), bytes);
+}
+
+#[test]
+fn maps_addresses_inside_the_text_segment() {
+    let dir = tempfile::tempdir().unwrap();
+    let src = dir.path().join("SCUS_944.25");
+    fs::write(&src, exe_file(0x8001_0000, 0x1000, &[])).unwrap();
+
+    let exe = Exe::load(src).unwrap();
+
+    assert!(!exe.contains(0x8000_ffff));
+    assert!(exe.contains(0x8001_0000));
+    assert!(exe.contains(0x8001_0fff));
+    assert!(!exe.contains(0x8001_1000));
+
+    assert_eq!(exe.file_offset(0x8001_0000), Some(EXE_HEADER_LEN as u64));
+    assert_eq!(
+        exe.file_offset(0x8001_0fff),
+        Some(EXE_HEADER_LEN as u64 + 0xfff)
+    );
+    assert_eq!(exe.file_offset(0x8001_1000), None);
+    assert_eq!(exe.address(EXE_HEADER_LEN as u64 + 0x10), Some(0x8001_0010));
+    assert_eq!(exe.read_u32(0x8001_0004).unwrap(), 0x0706_0504);
+}
+
+#[test]
+fn rejects_text_past_the_end_of_the_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let src = dir.path().join("SCUS_944.25");
+
+    let mut bytes = exe_file(0x8001_0000, 0x1000, &[]);
+    bytes.truncate(bytes.len() - 1);
+    fs::write(&src, bytes).unwrap();
+
+    assert!(Exe::load(src).is_err());
+}
+
+#[test]
+fn rejects_text_that_wraps_around_the_address_space() {
+    let dir = tempfile::tempdir().unwrap();
+    let src = dir.path().join("SCUS_944.25");
+    fs::write(&src, exe_file(0xffff_f800, 0x1000, &[])).unwrap();
+
+    assert!(Exe::load(src).is_err());
+}
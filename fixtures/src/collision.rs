@@ -0,0 +1,152 @@
+use proptest::prelude::*;
+
+use crate::{blob, noise, push_u32, triangles::packed_triangles};
+
+/// Data of the sections the parser only copies, as noise or seed and count
+#[derive(Clone, Debug)]
+struct Sections {
+    section_0: Vec<u8>,
+    /// In 28 byte records
+    section_1: (u64, usize),
+    /// In 4 byte offsets
+    section_2_offsets: (u64, usize),
+    section_2: Vec<u8>,
+    section_3: Vec<u8>,
+    section_5: Vec<u8>,
+    collision_types: Vec<u8>,
+    section_7: Vec<u8>,
+    section_9: Vec<u8>,
+    section_10: Vec<u8>,
+    section_11: Vec<u8>,
+    section_12: Vec<u8>,
+    section_14: Vec<u8>,
+    tail: Vec<u8>,
+}
+
+fn sections() -> impl Strategy<Value = Sections> {
+    (
+        (
+            blob(256),
+            (any::<u64>(), 0..16usize),
+            (any::<u64>(), 0..16usize),
+        ),
+        (blob(256), blob(256), blob(256)),
+        (blob(256), blob(256), blob(256)),
+        (blob(256), blob(256), blob(256)),
+        (blob(256), blob(256)),
+    )
+        .prop_map(
+            |(
+                (section_0, section_1, section_2_offsets),
+                (section_2, section_3, section_5),
+                (collision_types, section_7, section_9),
+                (section_10, section_11, section_12),
+                (section_14, tail),
+            )| Sections {
+                section_0,
+                section_1,
+                section_2_offsets,
+                section_2,
+                section_3,
+                section_5,
+                collision_types,
+                section_7,
+                section_9,
+                section_10,
+                section_11,
+                section_12,
+                section_14,
+                tail,
+            },
+        )
+}
+
+/// Appends a section whose header starts with the offset of the next
+/// section, counted from the start of the header
+fn push_section(bytes: &mut Vec<u8>, fields: &[u32], data: &[u8]) {
+    push_u32(bytes, (fields.len() * 4 + 4 + data.len()) as u32);
+    fields.iter().for_each(|field| push_u32(bytes, *field));
+    bytes.extend(data);
+}
+
+/// A collision file with every section present, section 8's tables hold
+/// packed triangles and noise, missing tables have a 0 offset
+pub fn collision() -> impl Strategy<Value = Vec<u8>> {
+    (
+        sections(),
+        packed_triangles(),
+        prop::collection::vec(prop::option::of(blob(128)), 7),
+        prop::array::uniform8(any::<u32>()),
+    )
+        .prop_map(|(sections, triangles, tables, fields)| {
+            let mut bytes = Vec::new();
+
+            push_section(&mut bytes, &[fields[0]], &sections.section_0);
+
+            let section_1 = noise(sections.section_1.0, sections.section_1.1 * 28);
+            let section_2_offsets = noise(
+                sections.section_2_offsets.0,
+                sections.section_2_offsets.1 * 4,
+            );
+
+            // section 1's header also points past section 2
+            let section_3_offset =
+                8 + section_1.len() + 8 + section_2_offsets.len() + sections.section_2.len();
+            push_u32(&mut bytes, section_3_offset as u32);
+            push_u32(&mut bytes, 4 + section_1.len() as u32);
+            bytes.extend(&section_1);
+
+            push_u32(&mut bytes, sections.section_2_offsets.1 as u32);
+            push_u32(&mut bytes, fields[1]);
+            bytes.extend(&section_2_offsets);
+            bytes.extend(&sections.section_2);
+
+            push_section(&mut bytes, &[], &sections.section_3);
+            push_section(&mut bytes, &[], &sections.section_5);
+            push_section(&mut bytes, &[fields[2]], &sections.collision_types);
+            push_section(&mut bytes, &[], &sections.section_7);
+
+            // vec_3
+            bytes.extend(noise(fields[3] as u64, 12));
+
+            // idfk, unk_0, unk_1, unk_2, then triangles, flags, unk_3, unk_4
+            let mut tables = tables;
+            tables.insert(4, Some(triangles.clone()));
+
+            let mut table_offsets = Vec::new();
+            let mut table_data: Vec<u8> = Vec::new();
+            // tables count from section 8's start, after its 40 byte header
+            let mut offset = 44;
+
+            for table in tables.iter() {
+                match table {
+                    Some(data) => {
+                        table_offsets.push(offset);
+                        table_data.extend(data);
+                        offset += data.len() as u32;
+                    }
+                    None => table_offsets.push(0),
+                }
+            }
+
+            push_u32(&mut bytes, offset + 4);
+            push_u32(&mut bytes, triangles.len() as u32 / 12);
+            table_offsets
+                .iter()
+                .for_each(|offset| push_u32(&mut bytes, *offset));
+            bytes.extend(table_data);
+
+            push_section(&mut bytes, &[], &sections.section_9);
+            push_section(&mut bytes, &[fields[4]], &sections.section_10);
+            push_section(&mut bytes, &[fields[5]], &sections.section_11);
+            push_section(&mut bytes, &[fields[6]], &sections.section_12);
+
+            // section 13
+            bytes.extend(noise(fields[7] as u64, 32));
+
+            push_section(&mut bytes, &[], &sections.section_14);
+            bytes.extend(&sections.tail);
+
+            bytes
+        })
+}
@@ -0,0 +1,231 @@
+use proptest::prelude::*;
+
+use crate::{align, blob, noise, push_u16, push_u32};
+
+/// Two 512x256 16bpp VRAM pages
+const TEX_LEN: usize = 512 * 1024;
+
+#[derive(Clone, Debug)]
+struct Sound {
+    /// 0 for an unused entry
+    size: u32,
+    pitch: u16,
+    id: u16,
+    /// Only used by unused entries, real ones point at their sample
+    spu_address: u32,
+    seed: u64,
+}
+
+#[derive(Clone, Debug)]
+struct SkySector {
+    vertex_count: u16,
+    color_count: u16,
+    poly_count: u16,
+    seed: u64,
+}
+
+#[derive(Clone, Debug)]
+struct MobySlot {
+    slot: usize,
+    /// Few distinct ids, so slots share them
+    id: u16,
+    /// Taken modulo the mobys section length
+    offset: u32,
+}
+
+fn sound() -> impl Strategy<Value = Sound> {
+    (
+        prop_oneof![Just(0u32), 1..2048u32],
+        any::<u16>(),
+        any::<u16>(),
+        any::<u32>(),
+        any::<u64>(),
+    )
+        .prop_map(|(size, pitch, id, spu_address, seed)| Sound {
+            size,
+            pitch,
+            id,
+            spu_address,
+            seed,
+        })
+}
+
+fn sky_sector() -> impl Strategy<Value = SkySector> {
+    (0..8u16, 0..8u16, 0..8u16, any::<u64>()).prop_map(
+        |(vertex_count, color_count, poly_count, seed)| SkySector {
+            vertex_count,
+            color_count,
+            poly_count,
+            seed,
+        },
+    )
+}
+
+fn moby_slot() -> impl Strategy<Value = MobySlot> {
+    (0..64usize, 0..4u16, any::<u32>()).prop_map(|(slot, id, offset)| MobySlot { slot, id, offset })
+}
+
+/// Sound bank header followed by the samples, each 16 byte aligned
+fn sound_bank(spu_base: u32, sounds: &[Sound]) -> Vec<u8> {
+    let mut samples = Vec::new();
+    let mut entries = Vec::new();
+
+    for sound in sounds {
+        let spu_address = if sound.size == 0 {
+            sound.spu_address
+        } else {
+            align(&mut samples, 16);
+            let spu_address = spu_base + samples.len() as u32;
+            samples.extend(noise(sound.seed, sound.size as usize));
+
+            spu_address
+        };
+
+        push_u32(&mut entries, spu_address);
+        push_u32(&mut entries, sound.size);
+        push_u16(&mut entries, sound.pitch);
+        push_u16(&mut entries, sound.id);
+    }
+
+    let mut bytes = Vec::new();
+    push_u32(&mut bytes, sounds.len() as u32);
+    push_u32(&mut bytes, spu_base);
+    bytes.extend(entries);
+    bytes.extend(samples);
+
+    bytes
+}
+
+fn sky(background: u32, sectors: &[SkySector]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    push_u32(&mut bytes, background);
+    push_u32(&mut bytes, sectors.len() as u32);
+
+    let mut sector_data = Vec::new();
+    let table_len = 8 + sectors.len() * 4;
+
+    for sector in sectors {
+        push_u32(&mut bytes, (table_len + sector_data.len()) as u32);
+
+        let data_len = 8 * sector.vertex_count + 4 * sector.color_count + 8 * sector.poly_count;
+        let mut data = noise(sector.seed, 8 + data_len as usize);
+
+        // counts go after the bounding sphere
+        let mut counts = Vec::new();
+        push_u16(&mut counts, sector.vertex_count);
+        push_u16(&mut counts, sector.color_count);
+        push_u16(&mut counts, sector.poly_count);
+        push_u16(&mut counts, 0);
+
+        data.splice(8..8, counts);
+        sector_data.extend(data);
+    }
+
+    bytes.extend(sector_data);
+
+    bytes
+}
+
+/// A level file whose sections all decode: textures, a sound bank, a sky,
+/// portals and object models, with the rest as noise. Sections start on
+/// 2048 byte boundaries, the padding is noise too.
+pub fn level() -> impl Strategy<Value = Vec<u8>> {
+    (
+        any::<u64>(),
+        0..0x7000_0000u32,
+        // extracted sounds are named by id, a real bank never repeats one
+        prop::collection::vec(sound(), 0..8).prop_filter("sound ids repeat", |sounds| {
+            let mut ids: Vec<u16> = sounds.iter().map(|sound| sound.id).collect();
+            ids.sort_unstable();
+            ids.dedup();
+
+            ids.len() == sounds.len()
+        }),
+        0..64usize,
+        [blob(4096), blob(4096), blob(512), blob(512)],
+        any::<u32>(),
+        prop::collection::vec(sky_sector(), 0..4),
+        (any::<u64>(), 1..4096usize),
+        prop::collection::vec(moby_slot(), 0..8),
+        (any::<u64>(), 0..6usize),
+        any::<u64>(),
+    )
+        .prop_map(
+            |(
+                tex_seed,
+                spu_base,
+                sounds,
+                bank_padding,
+                [collision_data, model, unk_3, unk_4],
+                background,
+                sky_sectors,
+                (mobys_seed, mobys_len),
+                moby_slots,
+                (portals_seed, portal_count),
+                padding_seed,
+            )| {
+                let mut padding = noise(padding_seed, 2048 * 10).into_iter();
+                let mut pad = |bytes: &mut Vec<u8>, len: usize| {
+                    bytes.extend(padding.by_ref().take(len));
+                };
+
+                let mut tex_and_audio = noise(tex_seed, TEX_LEN);
+                tex_and_audio.extend(sound_bank(spu_base, &sounds));
+                pad(&mut tex_and_audio, bank_padding);
+
+                let mut portals = Vec::new();
+                push_u32(&mut portals, portal_count as u32);
+                portals.extend(noise(portals_seed, portal_count * 16));
+
+                let sections = [
+                    tex_and_audio,
+                    collision_data,
+                    model,
+                    sky(background, &sky_sectors),
+                    noise(mobys_seed, mobys_len),
+                    portals,
+                    unk_3,
+                    unk_4,
+                ];
+
+                // header is rewritten once the offsets are known
+                let mut bytes = Vec::new();
+                pad(&mut bytes, 2048);
+                let mut header = Vec::new();
+                let mut mobys_offset = 0;
+
+                for (i, section) in sections.iter().enumerate() {
+                    if !section.is_empty() {
+                        let len = bytes.len().next_multiple_of(2048) - bytes.len();
+                        pad(&mut bytes, len);
+                    }
+
+                    if i == 4 {
+                        mobys_offset = bytes.len() as u32;
+                    }
+
+                    push_u32(&mut header, bytes.len() as u32);
+                    push_u32(&mut header, section.len() as u32);
+
+                    bytes.extend(section);
+                }
+
+                let mut moby_offsets = [0u32; 64];
+                let mut moby_ids = [0u16; 64];
+
+                for moby in moby_slots {
+                    moby_offsets[moby.slot] = mobys_offset + moby.offset % mobys_len as u32;
+                    moby_ids[moby.slot] = moby.id;
+                }
+
+                moby_offsets
+                    .iter()
+                    .for_each(|offset| push_u32(&mut header, *offset));
+                moby_ids.iter().for_each(|id| push_u16(&mut header, *id));
+
+                bytes[..header.len()].copy_from_slice(&header);
+
+                bytes
+            },
+        )
+}
@@ -0,0 +1,44 @@
+//! Synthetic game files for tests, built from random layouts so the format
+//! crates can be tested without a disc image.
+
+use proptest::prelude::*;
+
+pub mod collision;
+pub mod level;
+pub mod model;
+pub mod triangles;
+pub mod wad;
+
+/// `len` bytes of xorshift noise, generating them through proptest byte by
+/// byte is too slow for level sized files
+pub fn noise(seed: u64, len: usize) -> Vec<u8> {
+    let mut state = seed | 1;
+
+    (0..len)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+
+            state as u8
+        })
+        .collect()
+}
+
+/// Noise of up to `max_len` bytes
+pub fn blob(max_len: usize) -> impl Strategy<Value = Vec<u8>> {
+    (any::<u64>(), 0..=max_len).prop_map(|(seed, len)| noise(seed, len))
+}
+
+fn push_u16(bytes: &mut Vec<u8>, value: u16) {
+    bytes.extend(value.to_le_bytes());
+}
+
+fn push_u32(bytes: &mut Vec<u8>, value: u32) {
+    bytes.extend(value.to_le_bytes());
+}
+
+/// Zero pads `bytes` to a multiple of `alignment`
+fn align(bytes: &mut Vec<u8>, alignment: usize) {
+    bytes.resize(bytes.len().next_multiple_of(alignment), 0);
+}
@@ -0,0 +1,91 @@
+use proptest::prelude::*;
+
+use crate::{noise, push_u32};
+
+#[derive(Clone, Debug)]
+struct Sector {
+    /// Low then high LOD vertex, colour and polygon counts
+    lp_counts: [u8; 3],
+    hp_counts: [u8; 3],
+    seed: u64,
+}
+
+fn counts() -> impl Strategy<Value = [u8; 3]> {
+    // polygons need something to index
+    (0..16u8, 0..16u8, 0..16u8).prop_map(|(vertices, colors, polys)| {
+        let polys = if vertices == 0 || colors == 0 {
+            0
+        } else {
+            polys
+        };
+
+        [vertices, colors, polys]
+    })
+}
+
+fn sector() -> impl Strategy<Value = Sector> {
+    (counts(), counts(), any::<u64>()).prop_map(|(lp_counts, hp_counts, seed)| Sector {
+        lp_counts,
+        hp_counts,
+        seed,
+    })
+}
+
+/// Vertex indices then colour indices of a polygon, in range of `counts`
+fn poly_indices(bytes: &mut Vec<u8>, counts: [u8; 3], noise: &[u8]) {
+    bytes.extend(noise[..4].iter().map(|index| index % counts[0]));
+    bytes.extend(noise[4..8].iter().map(|index| index % counts[1]));
+}
+
+/// One LOD of a sector: packed vertices, colours and polygons whose
+/// indices are in range, `poly_len` bytes each
+fn lod(bytes: &mut Vec<u8>, counts: [u8; 3], poly_len: usize, seed: u64) {
+    let [vertices, colors, polys] = counts.map(|count| count as usize);
+
+    bytes.extend(noise(seed, vertices * 4 + colors * 4));
+
+    let poly_noise = noise(seed ^ 1, polys * poly_len);
+    for poly in poly_noise.chunks(poly_len) {
+        poly_indices(bytes, counts, poly);
+        bytes.extend(&poly[8..]);
+    }
+}
+
+/// A model section: texture references, then sectors with both LODs
+pub fn model() -> impl Strategy<Value = Vec<u8>> {
+    (
+        any::<u64>(),
+        0..8usize,
+        prop::collection::vec(sector(), 0..8),
+    )
+        .prop_map(|(seed, texture_count, sectors)| {
+            let mut bytes = Vec::new();
+
+            push_u32(&mut bytes, texture_count as u32);
+            bytes.extend(noise(seed, texture_count * 12));
+
+            push_u32(&mut bytes, sectors.len() as u32);
+            let table_end = bytes.len() + sectors.len() * 4;
+
+            let mut sector_data = Vec::new();
+            for sector in sectors.iter() {
+                push_u32(&mut bytes, (table_end + sector_data.len()) as u32);
+
+                // bounding sphere, origin and padding
+                sector_data.extend(noise(sector.seed, 16));
+                sector_data.extend(sector.lp_counts);
+                sector_data.push(0);
+                sector_data.extend(sector.hp_counts);
+                sector_data.push(0);
+                // flags and unk
+                sector_data.extend(noise(sector.seed ^ 2, 8));
+
+                lod(&mut sector_data, sector.lp_counts, 8, sector.seed ^ 3);
+                lod(&mut sector_data, sector.hp_counts, 16, sector.seed ^ 4);
+            }
+
+            bytes.extend(sector_data);
+
+            bytes
+        })
+}
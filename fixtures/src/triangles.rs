@@ -0,0 +1,19 @@
+use proptest::prelude::*;
+
+use crate::push_u32;
+
+/// Packed collision triangles, 12 bytes each. Bits 14 and 15 of the z word
+/// aren't part of the format and stay clear.
+pub fn packed_triangles() -> impl Strategy<Value = Vec<u8>> {
+    prop::collection::vec((any::<u32>(), any::<u32>(), any::<u32>()), 0..64).prop_map(|triangles| {
+        let mut bytes = Vec::new();
+
+        for (x, y, z) in triangles {
+            push_u32(&mut bytes, x);
+            push_u32(&mut bytes, y);
+            push_u32(&mut bytes, z & !0xc000);
+        }
+
+        bytes
+    })
+}
@@ -0,0 +1,27 @@
+use proptest::prelude::*;
+
+use crate::{blob, push_u32};
+
+/// Files to pack, any count the 256 entry table holds
+pub fn files() -> impl Strategy<Value = Vec<Vec<u8>>> {
+    prop::collection::vec(blob(4096), 1..32)
+}
+
+/// Packs `files` the way the game's WAD.WAD is laid out, a 2048 byte table
+/// followed by the files back to back
+pub fn wad(files: &[Vec<u8>]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    let mut offset = 2048;
+
+    for file in files {
+        push_u32(&mut bytes, offset);
+        push_u32(&mut bytes, file.len() as u32);
+
+        offset += file.len() as u32;
+    }
+
+    bytes.resize(2048, 0);
+    files.iter().for_each(|file| bytes.extend(file));
+
+    bytes
+}
@@ -0,0 +1,65 @@
+use std::{
+    error::Error,
+    io,
+    path::{Path, PathBuf},
+};
+
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum LevelError {
+    #[error("{}", path.display())]
+    Io { path: PathBuf, source: io::Error },
+    /// Reading or decoding one section of the level file failed
+    #[error("{}: {section} at offset 0x{offset:x}", path.display())]
+    Section {
+        path: PathBuf,
+        section: String,
+        offset: u64,
+        source: Box<dyn Error + Send + Sync>,
+    },
+    /// Writing one of the JSON files next to the extracted sections failed
+    #[error("{}", path.display())]
+    Json {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+    /// A header value that can't be right, checked before anything is read
+    #[error("{}: {section} at offset 0x{offset:x}: {reason}", path.display())]
+    Invalid {
+        path: PathBuf,
+        section: String,
+        offset: u64,
+        reason: String,
+    },
+}
+
+impl LevelError {
+    pub fn io(path: &Path) -> impl FnOnce(io::Error) -> Self {
+        let path = path.to_path_buf();
+
+        move |source| Self::Io { path, source }
+    }
+
+    pub fn json(path: &Path) -> impl FnOnce(serde_json::Error) -> Self {
+        let path = path.to_path_buf();
+
+        move |source| Self::Json { path, source }
+    }
+
+    pub fn section<E: Into<Box<dyn Error + Send + Sync>>>(
+        path: &Path,
+        section: impl Into<String>,
+        offset: u64,
+    ) -> impl FnOnce(E) -> Self {
+        let path = path.to_path_buf();
+        let section = section.into();
+
+        move |source| Self::Section {
+            path,
+            section,
+            offset,
+            source: source.into(),
+        }
+    }
+}
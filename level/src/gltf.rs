@@ -0,0 +1,352 @@
+use std::{collections::BTreeMap, fs::File, io::Write, path::PathBuf};
+
+use serde_json::{Value, json};
+
+use crate::{
+    LevelManifest,
+    model::{Color, HighPoly, Model, Sector, TextureRef, Vertex, parse_model},
+    vram::{Vram, texture_bounds},
+};
+
+/// Same scale and axes as the collision OBJ export
+const SCALE: f32 = 1.0 / 256.0;
+
+const GLB_MAGIC: u32 = 0x4654_6c67;
+const CHUNK_JSON: u32 = 0x4e4f_534a;
+const CHUNK_BIN: u32 = 0x004e_4942;
+
+const FLOAT: u32 = 5126;
+const NEAREST: u32 = 9728;
+
+/// Minimal binary glTF writer, everything goes into a single buffer
+#[derive(Default)]
+pub struct GlbBuilder {
+    buffer: Vec<u8>,
+    buffer_views: Vec<Value>,
+    accessors: Vec<Value>,
+    images: Vec<Value>,
+    textures: Vec<Value>,
+    materials: Vec<Value>,
+    meshes: Vec<Value>,
+    nodes: Vec<Value>,
+    animations: Vec<Value>,
+}
+
+impl GlbBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn push_view(&mut self, data: &[u8]) -> usize {
+        while !self.buffer.len().is_multiple_of(4) {
+            self.buffer.push(0);
+        }
+
+        self.buffer_views.push(json!({
+            "buffer": 0,
+            "byteOffset": self.buffer.len(),
+            "byteLength": data.len(),
+        }));
+        self.buffer.extend_from_slice(data);
+
+        self.buffer_views.len() - 1
+    }
+
+    /// Adds a float accessor of `N` components per element
+    pub fn push_accessor<const N: usize>(&mut self, data: &[[f32; N]], bounds: bool) -> usize {
+        let bytes = data
+            .iter()
+            .flatten()
+            .flat_map(|f| f.to_le_bytes())
+            .collect::<Vec<_>>();
+
+        let view = self.push_view(&bytes);
+
+        let kind = match N {
+            2 => "VEC2",
+            3 => "VEC3",
+            4 => "VEC4",
+            _ => "SCALAR",
+        };
+
+        let mut accessor = json!({
+            "bufferView": view,
+            "componentType": FLOAT,
+            "count": data.len(),
+            "type": kind,
+        });
+
+        // required for POSITION
+        if bounds {
+            let mut min = [f32::MAX; N];
+            let mut max = [f32::MIN; N];
+
+            for element in data {
+                for i in 0..N {
+                    min[i] = min[i].min(element[i]);
+                    max[i] = max[i].max(element[i]);
+                }
+            }
+
+            accessor["min"] = json!(min.to_vec());
+            accessor["max"] = json!(max.to_vec());
+        }
+
+        self.accessors.push(accessor);
+
+        self.accessors.len() - 1
+    }
+
+    /// Adds a PNG image and a material sampling it with nearest filtering
+    pub fn push_textured_material(&mut self, png: &[u8]) -> usize {
+        let view = self.push_view(png);
+
+        self.images.push(json!({
+            "bufferView": view,
+            "mimeType": "image/png",
+        }));
+        self.textures.push(json!({
+            "source": self.images.len() - 1,
+            "sampler": 0,
+        }));
+        self.materials.push(json!({
+            "pbrMetallicRoughness": {
+                "baseColorTexture": { "index": self.textures.len() - 1 },
+                "metallicFactor": 0.0,
+            },
+            "alphaMode": "MASK",
+            "doubleSided": true,
+        }));
+
+        self.materials.len() - 1
+    }
+
+    pub fn push_color_material(&mut self) -> usize {
+        self.materials.push(json!({
+            "pbrMetallicRoughness": { "metallicFactor": 0.0 },
+            "doubleSided": true,
+        }));
+
+        self.materials.len() - 1
+    }
+
+    pub fn push_mesh(&mut self, mesh: Value) -> usize {
+        self.meshes.push(mesh);
+
+        self.meshes.len() - 1
+    }
+
+    pub fn push_node(&mut self, node: Value) -> usize {
+        self.nodes.push(node);
+
+        self.nodes.len() - 1
+    }
+
+    pub fn push_animation(&mut self, animation: Value) -> usize {
+        self.animations.push(animation);
+
+        self.animations.len() - 1
+    }
+
+    pub fn write(self, output_file: PathBuf) -> anyhow::Result<()> {
+        let mut gltf = json!({
+            "asset": { "version": "2.0", "generator": "s2" },
+            "scene": 0,
+            "scenes": [{ "nodes": (0..self.nodes.len()).collect::<Vec<_>>() }],
+            "nodes": self.nodes,
+            "meshes": self.meshes,
+            "materials": self.materials,
+            "accessors": self.accessors,
+            "bufferViews": self.buffer_views,
+            "buffers": [{ "byteLength": self.buffer.len() }],
+        });
+
+        if !self.images.is_empty() {
+            gltf["images"] = json!(self.images);
+            gltf["textures"] = json!(self.textures);
+            gltf["samplers"] = json!([{ "magFilter": NEAREST, "minFilter": NEAREST }]);
+        }
+
+        if !self.animations.is_empty() {
+            gltf["animations"] = json!(self.animations);
+        }
+
+        let mut json_chunk = serde_json::to_vec(&gltf)?;
+        while !json_chunk.len().is_multiple_of(4) {
+            json_chunk.push(b' ');
+        }
+
+        let mut bin_chunk = self.buffer;
+        while !bin_chunk.len().is_multiple_of(4) {
+            bin_chunk.push(0);
+        }
+
+        let total_len = 12 + 8 + json_chunk.len() + 8 + bin_chunk.len();
+
+        let mut dst_file = File::create(output_file)?;
+
+        dst_file.write_all(&GLB_MAGIC.to_le_bytes())?;
+        dst_file.write_all(&2u32.to_le_bytes())?;
+        dst_file.write_all(&(total_len as u32).to_le_bytes())?;
+
+        dst_file.write_all(&(json_chunk.len() as u32).to_le_bytes())?;
+        dst_file.write_all(&CHUNK_JSON.to_le_bytes())?;
+        dst_file.write_all(&json_chunk)?;
+
+        dst_file.write_all(&(bin_chunk.len() as u32).to_le_bytes())?;
+        dst_file.write_all(&CHUNK_BIN.to_le_bytes())?;
+        dst_file.write_all(&bin_chunk)?;
+
+        Ok(())
+    }
+}
+
+pub fn position(vertex: &Vertex) -> [f32; 3] {
+    [
+        vertex.x as f32 * SCALE,
+        vertex.z as f32 * SCALE,
+        vertex.y as f32 * -SCALE,
+    ]
+}
+
+pub fn color(color: &Color) -> [f32; 3] {
+    [
+        color.r as f32 / 255.0,
+        color.g as f32 / 255.0,
+        color.b as f32 / 255.0,
+    ]
+}
+
+/// Corners of the triangles making up a polygon
+pub fn triangle_corners(is_triangle: bool) -> &'static [usize] {
+    if is_triangle {
+        &[1, 2, 3]
+    } else {
+        &[0, 1, 2, 1, 3, 2]
+    }
+}
+
+// triangle soup of the polygons sharing one material
+#[derive(Default)]
+struct Primitive {
+    positions: Vec<[f32; 3]>,
+    colors: Vec<[f32; 3]>,
+    uvs: Vec<[f32; 2]>,
+}
+
+impl Primitive {
+    fn push_poly(
+        &mut self,
+        sector: &Sector,
+        poly: &HighPoly,
+        texture: Option<&TextureRef>,
+    ) -> anyhow::Result<()> {
+        for &corner in triangle_corners(poly.is_triangle()) {
+            let index = poly.vertices[corner];
+            let Some(vertex) = sector.hp_vertices.get(index as usize) else {
+                anyhow::bail!("vertex {index} out of range");
+            };
+
+            let index = poly.colors[corner];
+            let Some(vertex_color) = sector.hp_colors.get(index as usize) else {
+                anyhow::bail!("colour {index} out of range");
+            };
+
+            self.positions.push(position(vertex));
+            self.colors.push(color(vertex_color));
+
+            if let Some(texture) = texture {
+                let (u0, v0, u1, v1) = texture_bounds(texture);
+                let (u, v) = texture.uvs[corner];
+
+                // the image holds texels u0..=u1, so it's one wider than u1 - u0
+                self.uvs.push([
+                    (u - u0) as f32 / ((u1 - u0) as f32 + 1.0),
+                    (v - v0) as f32 / ((v1 - v0) as f32 + 1.0),
+                ]);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn sector_mesh(
+    builder: &mut GlbBuilder,
+    model: &Model,
+    sector: &Sector,
+    materials: &[usize],
+    color_material: usize,
+) -> anyhow::Result<Value> {
+    // keyed by texture index, None for untextured polygons
+    let mut primitives: BTreeMap<Option<usize>, Primitive> = BTreeMap::new();
+
+    for poly in sector.hp_polys.iter() {
+        let texture =
+            Some(poly.texture as usize).filter(|i| poly.is_textured() && *i < model.textures.len());
+
+        primitives.entry(texture).or_default().push_poly(
+            sector,
+            poly,
+            texture.map(|i| &model.textures[i]),
+        )?;
+    }
+
+    let primitives = primitives
+        .into_iter()
+        .map(|(texture, primitive)| {
+            let mut attributes = json!({
+                "POSITION": builder.push_accessor(&primitive.positions, true),
+                "COLOR_0": builder.push_accessor(&primitive.colors, false),
+            });
+
+            if !primitive.uvs.is_empty() {
+                attributes["TEXCOORD_0"] = json!(builder.push_accessor(&primitive.uvs, false));
+            }
+
+            json!({
+                "attributes": attributes,
+                "material": texture.map(|i| materials[i]).unwrap_or(color_material),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    Ok(json!({ "primitives": primitives }))
+}
+
+/// Exports the high LOD scenery as a .glb, one node per sector
+pub fn export_level(level_manifest: &LevelManifest, output_file: PathBuf) -> anyhow::Result<()> {
+    let model = parse_model(level_manifest.model.clone())?;
+    let vram = Vram::from_level(&level_manifest.tex_0, &level_manifest.tex_1)?;
+
+    let mut builder = GlbBuilder::new();
+
+    let materials = model
+        .textures
+        .iter()
+        .map(|texture| {
+            let png = vram.texture_image(texture).to_png()?;
+
+            Ok(builder.push_textured_material(&png))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let color_material = builder.push_color_material();
+
+    for (i, sector) in model.sectors.iter().enumerate() {
+        if sector.hp_polys.is_empty() {
+            continue;
+        }
+
+        let mesh = sector_mesh(&mut builder, &model, sector, &materials, color_material)
+            .map_err(|e| e.context(format!("sector {i}")))?;
+        let mesh = builder.push_mesh(mesh);
+
+        builder.push_node(json!({
+            "name": format!("sector_{i}"),
+            "mesh": mesh,
+        }));
+    }
+
+    builder.write(output_file)
+}
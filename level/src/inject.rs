@@ -0,0 +1,206 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{Seek, Write},
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{read_level_header, sound::read_sound_bank_header};
+
+// 20 byte header written by write_16bpp_tim_header
+const TIM_HEADER_LEN: usize = 20;
+const VAG_HEADER_LEN: usize = 48;
+
+const TEX_PAGE_LEN: usize = 256 * 1024;
+const TEX_PAGE_WIDTH: u16 = 512;
+const TEX_PAGE_HEIGHT: u16 = 256;
+
+/// Pixels of a texture page, 16bpp
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq, Eq)]
+pub struct TextureRect {
+    pub x: u16,
+    pub y: u16,
+    pub width: u16,
+    pub height: u16,
+}
+
+impl TextureRect {
+    pub const PAGE: TextureRect = TextureRect {
+        x: 0,
+        y: 0,
+        width: TEX_PAGE_WIDTH,
+        height: TEX_PAGE_HEIGHT,
+    };
+
+    pub fn overlaps(&self, other: &TextureRect) -> bool {
+        let (x, y, other_x, other_y) =
+            (self.x as u32, self.y as u32, other.x as u32, other.y as u32);
+
+        x < other_x + other.width as u32
+            && other_x < x + self.width as u32
+            && y < other_y + other.height as u32
+            && other_y < y + self.height as u32
+    }
+}
+
+fn open_level(level_file: &Path) -> anyhow::Result<File> {
+    Ok(OpenOptions::new().read(true).write(true).open(level_file)?)
+}
+
+/// Copies `rect` of a 512x256 16bpp TIM into VRAM page 0 or 1 of a level,
+/// the rest of the page is left alone
+pub fn replace_texture_page(
+    level_file: PathBuf,
+    page: u8,
+    tim_file: &Path,
+    rect: TextureRect,
+) -> anyhow::Result<()> {
+    if page > 1 {
+        anyhow::bail!("levels only have texture pages 0 and 1, got {page}");
+    }
+
+    if rect.x as u32 + rect.width as u32 > TEX_PAGE_WIDTH as u32
+        || rect.y as u32 + rect.height as u32 > TEX_PAGE_HEIGHT as u32
+    {
+        anyhow::bail!(
+            "{}x{} at {},{} doesn't fit in a 512x256 texture page",
+            rect.width,
+            rect.height,
+            rect.x,
+            rect.y
+        );
+    }
+
+    let tim = fs::read(tim_file)?;
+
+    if tim.len() != TIM_HEADER_LEN + TEX_PAGE_LEN {
+        anyhow::bail!(
+            "{} must be a 512x256 16bpp TIM ({} bytes), got {} bytes",
+            tim_file.display(),
+            TIM_HEADER_LEN + TEX_PAGE_LEN,
+            tim.len()
+        );
+    }
+
+    let mut file = open_level(&level_file)?;
+    let header = read_level_header(&mut file)?;
+
+    let page_offset = header.tex_and_audio.offset as u64 + page as u64 * TEX_PAGE_LEN as u64;
+    let row_len = TEX_PAGE_WIDTH as usize * 2;
+
+    for y in rect.y as usize..rect.y as usize + rect.height as usize {
+        let start = y * row_len + rect.x as usize * 2;
+        let pixels = &tim[TIM_HEADER_LEN + start..][..rect.width as usize * 2];
+
+        file.seek(std::io::SeekFrom::Start(page_offset + start as u64))?;
+        file.write_all(pixels)?;
+    }
+
+    Ok(())
+}
+
+/// Replaces the samples of a sound in place, the new sound can't be larger
+/// than the original. The pitch is updated from the VAG sample rate.
+pub fn replace_sound(level_file: PathBuf, id: u16, vag_file: &Path) -> anyhow::Result<()> {
+    let vag = fs::read(vag_file)?;
+
+    if vag.len() < VAG_HEADER_LEN || &vag[0..4] != b"VAGp" {
+        anyhow::bail!("{} is not a VAG file", vag_file.display());
+    }
+
+    let sample_rate = u32::from_be_bytes([vag[16], vag[17], vag[18], vag[19]]);
+    let samples = &vag[VAG_HEADER_LEN..];
+
+    let mut file = open_level(&level_file)?;
+    let header = read_level_header(&mut file)?;
+
+    let bank_offset = header.tex_and_audio.offset as u64 + 2 * TEX_PAGE_LEN as u64;
+
+    file.seek(std::io::SeekFrom::Start(bank_offset))?;
+    let bank = read_sound_bank_header(&mut file)?;
+
+    let (index, entry) = bank
+        .entries
+        .iter()
+        .enumerate()
+        .find(|(_, entry)| entry.id == id)
+        .ok_or_else(|| anyhow::anyhow!("{}: no sound with id {id}", level_file.display()))?;
+
+    if samples.len() > entry.size as usize {
+        anyhow::bail!(
+            "{}: sound {id} is {} bytes, replacement is {}",
+            vag_file.display(),
+            entry.size,
+            samples.len()
+        );
+    }
+
+    let data_offset = bank_offset + bank.size();
+
+    let relative = entry
+        .spu_address
+        .checked_sub(bank.spu_base)
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "{}: sound {id} SPU address 0x{:x} is below the bank base 0x{:x}",
+                level_file.display(),
+                entry.spu_address,
+                bank.spu_base
+            )
+        })?;
+
+    // nothing is written unless the whole sample lands inside the section
+    let section_end = header.tex_and_audio.offset as u64 + header.tex_and_audio.length as u64;
+    if data_offset + relative as u64 + entry.size as u64 > section_end {
+        anyhow::bail!(
+            "{}: sound {id} at SPU address 0x{:x} runs past the end of the audio data",
+            level_file.display(),
+            entry.spu_address
+        );
+    }
+
+    let pitch = (sample_rate as u64 * 0x1000 / 44100) as u16;
+
+    // pitch is 8 bytes into the 12 byte entry
+    file.seek(std::io::SeekFrom::Start(
+        bank_offset + 8 + index as u64 * 12 + 8,
+    ))?;
+    file.write_all(&pitch.to_le_bytes())?;
+
+    file.seek(std::io::SeekFrom::Start(data_offset + relative as u64))?;
+    file.write_all(samples)?;
+    // silence whatever is left of the old sound
+    file.write_all(&vec![0u8; entry.size as usize - samples.len()])?;
+
+    Ok(())
+}
+
+/// Replaces the collision section, the new data can't be larger than the
+/// original since the following sections stay where they are
+pub fn replace_collision(level_file: PathBuf, collision_file: &Path) -> anyhow::Result<()> {
+    let collision = fs::read(collision_file)?;
+
+    let mut file = open_level(&level_file)?;
+    let header = read_level_header(&mut file)?;
+
+    if collision.len() > header.collision_data.length as usize {
+        anyhow::bail!(
+            "{}: collision data is {} bytes, at most {} fit",
+            collision_file.display(),
+            collision.len(),
+            header.collision_data.length
+        );
+    }
+
+    // length of the second header entry
+    file.seek(std::io::SeekFrom::Start(12))?;
+    file.write_all(&(collision.len() as u32).to_le_bytes())?;
+
+    file.seek(std::io::SeekFrom::Start(
+        header.collision_data.offset as u64,
+    ))?;
+    file.write_all(&collision)?;
+
+    Ok(())
+}
@@ -1,43 +1,236 @@
 use std::{
     fs::{File, create_dir_all},
-    io::{Read, Seek, Write, copy},
-    path::PathBuf,
+    io::{BufReader, Cursor, Read, Seek, Write},
+    ops::Range,
+    path::{Path, PathBuf},
 };
 
-use chrono::{DateTime, Local};
+use error::LevelError;
+use moby::{MobyData, MobyModel, extract_moby_models, read_moby_models};
+use portal::{Portal, parse_portals, read_portals};
 use serde::{Deserialize, Serialize};
-use wad::WADFile;
+use serde_json::{Map, Value};
+use sky::{SkyColors, read_sky};
+use sound::{SoundBank, SoundBankData, extract_sound_bank, read_sound_bank, write_sound_bank};
+use wad::{
+    Migration, Provenance, WADFile, manifest_dir, read_exact_vec, read_manifest,
+    rebase_legacy_path, relative_path, replace_timestamp, resolve_path,
+};
+
+pub mod error;
+pub mod gltf;
+pub mod inject;
+pub mod moby;
+pub mod model;
+pub mod portal;
+pub mod sky;
+pub mod sound;
+pub mod vram;
 
 #[derive(Debug)]
 pub struct LevelHeader {
     pub tex_and_audio: WADFile,
     pub collision_data: WADFile,
     pub model: WADFile,
-    pub something: [WADFile; 5],
-    pub some_offsets: [u32; 64],
-    pub model_indices: [u16; 64],
+    pub sky: WADFile,
+    /// Object models and animations
+    pub mobys: WADFile,
+    pub portals: WADFile,
+    // not understood yet, kept as raw files
+    pub unk_3: WADFile,
+    pub unk_4: WADFile,
+    /// Where each loaded object model lives in the level file, 0 if unused
+    pub moby_offsets: [u32; 64],
+    /// Global model ID of each slot
+    pub moby_ids: [u16; 64],
 }
 
-#[derive(Serialize, Deserialize)]
+pub const SCHEMA_VERSION: u32 = 2;
+
+const MIGRATIONS: [Migration; SCHEMA_VERSION as usize] = [migrate_unversioned, replace_timestamp];
+
+/// Sections the original level.json listed as `something`, in header order
+const UNVERSIONED_SECTIONS: [&str; 5] = ["sky", "mobys", "portals", "unk_3", "unk_4"];
+
+/// Upgrades a level.json written before manifests had a version, paths
+/// become relative to the manifest. The first of these stored the sky,
+/// mobys, portals and unknown sections as `something` and raw audio
+/// buffers, the sections decoded since are filled in from the extracted
+/// files. Nothing is written, the result only lives in memory.
+fn migrate_unversioned(manifest: &mut Map<String, Value>, base: &Path) -> anyhow::Result<()> {
+    if let Some(Value::Array(something)) = manifest.remove("something") {
+        if something.len() != UNVERSIONED_SECTIONS.len() {
+            anyhow::bail!("`something` lists {} files instead of 5", something.len());
+        }
+
+        for (field, path) in UNVERSIONED_SECTIONS.into_iter().zip(something) {
+            manifest.insert(field.into(), path);
+        }
+    }
+
+    // the sound bank replaces these (a_reverb.bin was the start of the bank,
+    // see SoundBankHeader), the moby table is read from the level
+    for field in ["reverb", "audio_buffers", "some_offsets", "model_indices"] {
+        manifest.remove(field);
+    }
+
+    for field in ["tex_0", "tex_1", "collision_data", "model", "sky_colors"]
+        .into_iter()
+        .chain(UNVERSIONED_SECTIONS)
+    {
+        match manifest.get_mut(field) {
+            Some(path) => rebase_legacy_path(path, base),
+            None if field == "sky_colors" => {}
+            None => anyhow::bail!("`{field}` is missing"),
+        }
+    }
+
+    let section_file = |manifest: &Map<String, Value>, field: &str| {
+        let path = manifest[field].as_str().unwrap_or_default();
+
+        // sections were extracted next to the level.json
+        resolve_path(base, base, Path::new(path))
+    };
+
+    // sky_colors is left out: writing sky_colors.json would touch the disk on
+    // every load, and without it the sky passes through like it always did
+    // for these manifests. Unpacking again exports it.
+
+    if !manifest.contains_key("portal_entries") {
+        let portals = parse_portals(section_file(manifest, "portals")?).ok();
+        manifest.insert("portal_entries".into(), serde_json::to_value(portals)?);
+    }
+
+    manifest
+        .entry("sound_bank")
+        .or_insert(serde_json::json!({ "spu_base": 0, "sounds": [] }));
+    manifest
+        .entry("moby_models")
+        .or_insert(Value::Array(Vec::new()));
+
+    let sounds = manifest
+        .get_mut("sound_bank")
+        .and_then(|bank| bank.get_mut("sounds"))
+        .and_then(Value::as_array_mut);
+    for sound in sounds.into_iter().flatten() {
+        if let Some(file) = sound.get_mut("file") {
+            rebase_legacy_path(file, base);
+        }
+    }
+
+    if let Some(Value::Array(moby_models)) = manifest.get_mut("moby_models") {
+        for moby_model in moby_models.iter_mut() {
+            if let Some(file) = moby_model.get_mut("file") {
+                rebase_legacy_path(file, base);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// level.json, paths are relative to its directory
+#[derive(Serialize, Deserialize, Clone)]
 pub struct LevelManifest {
-    pub timestamp: DateTime<Local>,
+    pub schema_version: u32,
+    pub provenance: Provenance,
     pub tex_0: PathBuf,
     pub tex_1: PathBuf,
-    pub reverb: PathBuf,
-    pub audio_buffers: Vec<PathBuf>,
+    pub sound_bank: SoundBank,
     pub collision_data: PathBuf,
     pub model: PathBuf,
-    pub something: Vec<PathBuf>,
-    pub some_offsets: Vec<u32>,
-    pub model_indices: Vec<u16>,
+    pub sky: PathBuf,
+    /// Missing when the sky didn't decode, the sky is then passed through
+    #[serde(default)]
+    pub sky_colors: Option<PathBuf>,
+    pub mobys: PathBuf,
+    pub portals: PathBuf,
+    #[serde(default)]
+    pub portal_entries: Option<Vec<Portal>>,
+    pub unk_3: PathBuf,
+    pub unk_4: PathBuf,
+    pub moby_models: Vec<MobyModel>,
 }
 
-fn copy_limited(file: &mut File, dst_file: &mut File, remaining: u64) -> anyhow::Result<()> {
-    let mut limited = Read::by_ref(file).take(remaining);
+impl LevelManifest {
+    fn map_paths(&mut self, f: impl Fn(&Path) -> anyhow::Result<PathBuf>) -> anyhow::Result<()> {
+        for path in [
+            &mut self.tex_0,
+            &mut self.tex_1,
+            &mut self.collision_data,
+            &mut self.model,
+            &mut self.sky,
+            &mut self.mobys,
+            &mut self.portals,
+            &mut self.unk_3,
+            &mut self.unk_4,
+        ] {
+            *path = f(path)?;
+        }
 
-    copy(&mut limited, dst_file)?;
+        if let Some(sky_colors) = self.sky_colors.as_mut() {
+            *sky_colors = f(sky_colors)?;
+        }
 
-    Ok(())
+        for sound in self.sound_bank.sounds.iter_mut() {
+            sound.file = f(&sound.file)?;
+        }
+
+        for moby_model in self.moby_models.iter_mut() {
+            moby_model.file = f(&moby_model.file)?;
+        }
+
+        Ok(())
+    }
+
+    /// Loads a level.json whose files must all be inside `root`
+    pub fn load(json_file: &Path, root: &Path) -> anyhow::Result<Self> {
+        let mut manifest: Self = read_manifest(json_file, &MIGRATIONS)?;
+        let base = manifest_dir(json_file);
+
+        manifest.map_paths(|path| resolve_path(root, base, path))?;
+
+        Ok(manifest)
+    }
+
+    pub fn save(&self, json_file: &Path, root: &Path) -> anyhow::Result<()> {
+        let mut manifest = self.clone();
+        let base = manifest_dir(json_file);
+
+        manifest.map_paths(|path| relative_path(root, base, path))?;
+
+        serde_json::to_writer_pretty(File::create(json_file)?, &manifest)?;
+
+        Ok(())
+    }
+}
+
+fn read_u8(file: &mut impl Read) -> anyhow::Result<u8> {
+    let mut buffer = [0u8; 1];
+    file.read_exact(&mut buffer)?;
+
+    Ok(buffer[0])
+}
+
+fn read_u16(file: &mut impl Read) -> anyhow::Result<u16> {
+    let mut buffer = [0u8; 2];
+    file.read_exact(&mut buffer)?;
+
+    Ok(u16::from_le_bytes(buffer))
+}
+
+fn read_i16(file: &mut impl Read) -> anyhow::Result<i16> {
+    let mut buffer = [0u8; 2];
+    file.read_exact(&mut buffer)?;
+
+    Ok(i16::from_le_bytes(buffer))
+}
+
+fn read_u32(file: &mut impl Read) -> anyhow::Result<u32> {
+    let mut buffer = [0u8; 4];
+    file.read_exact(&mut buffer)?;
+
+    Ok(u32::from_le_bytes(buffer))
 }
 
 fn write_16bpp_tim_header(
@@ -46,7 +239,7 @@ fn write_16bpp_tim_header(
     y: u16,
     w: u16,
     h: u16,
-) -> anyhow::Result<()> {
+) -> std::io::Result<()> {
     // TIM header for 16bpp, no CLUT
     let magic: u32 = 0x10; // TIM file identifier
     let flags: u32 = 0x02; // 16bpp, no CLUT
@@ -71,185 +264,515 @@ fn write_16bpp_tim_header(
     Ok(())
 }
 
-pub fn parse_level(level_file: PathBuf, output_dir: PathBuf) -> anyhow::Result<LevelManifest> {
-    let mut file = File::open(&level_file)?;
-    let mut header = LevelHeader {
-        tex_and_audio: WADFile {
-            offset: 0,
-            length: 0,
-        },
-        collision_data: WADFile {
-            offset: 0,
-            length: 0,
-        },
-        model: WADFile {
-            offset: 0,
-            length: 0,
-        },
-        something: [WADFile {
-            offset: 0,
-            length: 0,
-        }; 5],
-        some_offsets: [0; 64],
-        model_indices: [0; 64],
-    };
-
-    let mut offset = [0u8; 4];
-    let mut length = [0u8; 4];
+fn read_wad_file(file: &mut impl Read) -> anyhow::Result<WADFile> {
+    Ok(WADFile {
+        offset: read_u32(file)?,
+        length: read_u32(file)?,
+    })
+}
 
-    file.read_exact(&mut offset)?;
-    file.read_exact(&mut length)?;
+pub fn read_level_header(file: &mut impl Read) -> anyhow::Result<LevelHeader> {
+    let tex_and_audio = read_wad_file(file)?;
+    let collision_data = read_wad_file(file)?;
+    let model = read_wad_file(file)?;
+    let sky = read_wad_file(file)?;
+    let mobys = read_wad_file(file)?;
+    let portals = read_wad_file(file)?;
+    let unk_3 = read_wad_file(file)?;
+    let unk_4 = read_wad_file(file)?;
+
+    let mut moby_offsets = [0u32; 64];
+    for offset in moby_offsets.iter_mut() {
+        *offset = read_u32(file)?;
+    }
 
-    header.tex_and_audio = WADFile {
-        offset: u32::from_le_bytes(offset),
-        length: u32::from_le_bytes(length),
-    };
+    let mut moby_ids = [0u16; 64];
+    for id in moby_ids.iter_mut() {
+        *id = read_u16(file)?;
+    }
 
-    file.read_exact(&mut offset)?;
-    file.read_exact(&mut length)?;
+    Ok(LevelHeader {
+        tex_and_audio,
+        collision_data,
+        model,
+        sky,
+        mobys,
+        portals,
+        unk_3,
+        unk_4,
+        moby_offsets,
+        moby_ids,
+    })
+}
 
-    header.collision_data = WADFile {
-        offset: u32::from_le_bytes(offset),
-        length: u32::from_le_bytes(length),
+/// Size of the level header, sections start after it
+pub const LEVEL_HEADER_LEN: u64 = 8 * 8 + 64 * 4 + 64 * 2;
+
+/// Two 512x256 16bpp VRAM pages
+const TEX_LEN: u64 = 512 * 1024;
+
+/// Checks every section of the header lies between the header and the end
+/// of the file without overlapping another one, and every object model
+/// starts inside the mobys section
+pub fn validate_level_header(
+    header: &LevelHeader,
+    file_len: u64,
+    path: &Path,
+) -> Result<(), LevelError> {
+    let invalid = |section: &str, offset: u64, reason: String| LevelError::Invalid {
+        path: path.to_path_buf(),
+        section: section.into(),
+        offset,
+        reason,
     };
 
-    file.read_exact(&mut offset)?;
-    file.read_exact(&mut length)?;
-
-    header.model = WADFile {
-        offset: u32::from_le_bytes(offset),
-        length: u32::from_le_bytes(length),
-    };
+    let sections = [
+        ("tex_and_audio", header.tex_and_audio),
+        ("collision_data", header.collision_data),
+        ("model", header.model),
+        ("sky", header.sky),
+        ("mobys", header.mobys),
+        ("portals", header.portals),
+        ("unk_3", header.unk_3),
+        ("unk_4", header.unk_4),
+    ];
+
+    for (name, section) in sections {
+        let offset = section.offset as u64;
+        let end = offset + section.length as u64;
+
+        if section.length == 0 {
+            continue;
+        }
 
-    for i in 0..5 {
-        file.read_exact(&mut offset)?;
-        file.read_exact(&mut length)?;
+        if offset < LEVEL_HEADER_LEN {
+            return Err(invalid(
+                name,
+                offset,
+                format!("section starts inside the 0x{LEVEL_HEADER_LEN:x} byte header"),
+            ));
+        }
 
-        header.something[i] = WADFile {
-            offset: u32::from_le_bytes(offset),
-            length: u32::from_le_bytes(length),
-        };
+        if end > file_len {
+            return Err(invalid(
+                name,
+                offset,
+                format!(
+                    "0x{:x} bytes run past the end of the file (0x{file_len:x} bytes)",
+                    section.length
+                ),
+            ));
+        }
     }
 
-    for i in 0..64 {
-        file.read_exact(&mut offset)?;
-
-        header.some_offsets[i] = u32::from_le_bytes(offset);
+    let mut present = sections
+        .iter()
+        .filter(|(_, section)| section.length != 0)
+        .collect::<Vec<_>>();
+    present.sort_by_key(|(_, section)| section.offset);
+
+    for pair in present.windows(2) {
+        let (name, section) = pair[0];
+        let (next_name, next) = pair[1];
+        let end = section.offset as u64 + section.length as u64;
+
+        if (next.offset as u64) < end {
+            return Err(invalid(
+                next_name,
+                next.offset as u64,
+                format!(
+                    "section overlaps {name} (0x{:x}..0x{end:x})",
+                    section.offset
+                ),
+            ));
+        }
     }
 
-    let mut short_buffer = [0u8; 2];
-    for i in 0..64 {
-        file.read_exact(&mut short_buffer)?;
-
-        header.model_indices[i] = u16::from_le_bytes(short_buffer);
+    let mobys_start = header.mobys.offset as u64;
+    let mobys_end = mobys_start + header.mobys.length as u64;
+
+    for (slot, &offset) in header.moby_offsets.iter().enumerate() {
+        if offset != 0 && !(mobys_start..mobys_end).contains(&(offset as u64)) {
+            return Err(invalid(
+                "mobys",
+                offset as u64,
+                format!(
+                    "model of slot {slot} starts outside the mobys section (0x{mobys_start:x}..0x{mobys_end:x})"
+                ),
+            ));
+        }
     }
 
-    create_dir_all(&output_dir)?;
+    if (header.tex_and_audio.length as u64) < TEX_LEN {
+        return Err(invalid(
+            "tex_and_audio",
+            header.tex_and_audio.offset as u64,
+            format!(
+                "0x{:x} bytes can't hold the two texture pages (0x{TEX_LEN:x} bytes)",
+                header.tex_and_audio.length
+            ),
+        ));
+    }
 
-    let mut file = File::open(&level_file)?;
+    Ok(())
+}
 
-    file.seek(std::io::SeekFrom::Start(header.tex_and_audio.offset as u64))?;
+/// A level file decoded in memory, sections keep their raw bytes
+pub struct Level {
+    pub header: LevelHeader,
+    /// The two 512x256 16bpp VRAM pages
+    pub textures: [Vec<u8>; 2],
+    pub sound_bank: SoundBankData,
+    pub collision_data: Vec<u8>,
+    pub model: Vec<u8>,
+    pub sky: Vec<u8>,
+    /// None when the sky section didn't decode
+    pub sky_colors: Option<SkyColors>,
+    pub mobys: Vec<u8>,
+    pub moby_models: Vec<MobyData>,
+    pub portals: Vec<u8>,
+    /// None when the portal section didn't decode
+    pub portal_entries: Option<Vec<Portal>>,
+    pub unk_3: Vec<u8>,
+    pub unk_4: Vec<u8>,
+    /// Offset and bytes of every non-zero run outside the header, the
+    /// sections and the sounds, alignment padding isn't always zeros
+    pub gaps: Vec<(u64, Vec<u8>)>,
+}
 
-    let mut tex_0 = output_dir.clone();
-    tex_0.push("tex_0.tim");
+/// Reads every run of bytes outside `covered` that isn't all zeros
+fn read_gaps(
+    file: &mut (impl Read + Seek),
+    mut covered: Vec<Range<u64>>,
+    file_len: u64,
+) -> std::io::Result<Vec<(u64, Vec<u8>)>> {
+    covered.sort_by_key(|range| range.start);
+    covered.push(file_len..file_len);
+
+    let mut gaps = Vec::new();
+    let mut position = 0;
+
+    for range in covered {
+        if range.start > position {
+            file.seek(std::io::SeekFrom::Start(position))?;
+            let bytes = read_exact_vec(file, range.start - position)?;
+
+            if bytes.iter().any(|&byte| byte != 0) {
+                gaps.push((position, bytes));
+            }
+        }
 
-    let mut dst_file = File::create(&tex_0)?;
+        position = position.max(range.end);
+    }
 
-    write_16bpp_tim_header(&mut dst_file, 512, 0, 512, 256)?;
+    Ok(gaps)
+}
 
-    // tex part
-    copy_limited(&mut file, &mut dst_file, 256 * 1024)?;
+/// Decodes a level without touching the disk, `level_file` is only used in
+/// errors
+pub fn read_level(file: &mut (impl Read + Seek), level_file: &Path) -> Result<Level, LevelError> {
+    file.rewind().map_err(LevelError::io(level_file))?;
 
-    let mut tex_1 = output_dir.clone();
-    tex_1.push("tex_1.tim");
+    let header = read_level_header(file).map_err(LevelError::section(level_file, "header", 0))?;
 
-    let mut dst_file = File::create(&tex_1)?;
+    let file_len = file
+        .seek(std::io::SeekFrom::End(0))
+        .map_err(LevelError::io(level_file))?;
+    validate_level_header(&header, file_len, level_file)?;
 
-    write_16bpp_tim_header(&mut dst_file, 512, 256, 512, 256)?;
+    let mut read_section = |name: &str, offset: u64, length: u64| {
+        file.seek(std::io::SeekFrom::Start(offset))
+            .and_then(|_| read_exact_vec(file, length))
+            .map_err(LevelError::section(level_file, name, offset))
+    };
 
-    // tex part
-    copy_limited(&mut file, &mut dst_file, 256 * 1024)?;
+    let tex_offset = header.tex_and_audio.offset as u64;
+
+    let textures = [
+        read_section("texture page 0", tex_offset, TEX_LEN / 2)?,
+        read_section("texture page 1", tex_offset + TEX_LEN / 2, TEX_LEN / 2)?,
+    ];
+
+    let mut read_file =
+        |name: &str, wfile: WADFile| read_section(name, wfile.offset as u64, wfile.length as u64);
+
+    let collision_data = read_file("collision_data", header.collision_data)?;
+    let model = read_file("model", header.model)?;
+    let sky = read_file("sky", header.sky)?;
+    let mobys = read_file("mobys", header.mobys)?;
+    let portals = read_file("portals", header.portals)?;
+    let unk_3 = read_file("unk_3", header.unk_3)?;
+    let unk_4 = read_file("unk_4", header.unk_4)?;
+
+    // sound bank follows the two VRAM pages
+    let bank_offset = tex_offset + TEX_LEN;
+    let bank_end = tex_offset + header.tex_and_audio.length as u64;
+    let sound_bank = read_sound_bank(file, bank_offset, bank_end).map_err(LevelError::section(
+        level_file,
+        "sound bank",
+        bank_offset,
+    ))?;
 
-    file.seek(std::io::SeekFrom::Start(
-        header.tex_and_audio.offset as u64 + 512 * 1024,
+    let moby_models = read_moby_models(file, &header).map_err(LevelError::section(
+        level_file,
+        "mobys",
+        header.mobys.offset as u64,
     ))?;
 
-    let mut reverb = output_dir.clone();
-    reverb.push("a_reverb.bin");
+    // guessed formats, the raw sections stay the source of truth
+    let portal_entries = read_portals(&mut Cursor::new(&portals)).ok();
+    let sky_colors = read_sky(&mut Cursor::new(&sky), 0)
+        .ok()
+        .map(|sky| sky.colors());
+
+    let samples_offset = bank_offset + 8 + sound_bank.samples.len() as u64 * 12;
+
+    let covered = [
+        header.collision_data,
+        header.model,
+        header.sky,
+        header.mobys,
+        header.portals,
+        header.unk_3,
+        header.unk_4,
+    ]
+    .iter()
+    .map(|wfile| wfile.offset as u64..wfile.offset as u64 + wfile.length as u64)
+    .chain([0..LEVEL_HEADER_LEN, tex_offset..samples_offset])
+    .chain(
+        sound_bank
+            .samples
+            .iter()
+            .filter(|sample| !sample.data.is_empty())
+            .map(|sample| {
+                let start = samples_offset
+                    + sample.entry.spu_address.wrapping_sub(sound_bank.spu_base) as u64;
+
+                start..start + sample.data.len() as u64
+            }),
+    )
+    .collect();
+
+    let gaps = read_gaps(file, covered, file_len).map_err(LevelError::io(level_file))?;
+
+    Ok(Level {
+        header,
+        textures,
+        sound_bank,
+        collision_data,
+        model,
+        sky,
+        sky_colors,
+        mobys,
+        moby_models,
+        portals,
+        portal_entries,
+        unk_3,
+        unk_4,
+        gaps,
+    })
+}
 
-    let mut dst_file = File::create(&reverb)?;
+fn write_level_header(header: &LevelHeader) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(LEVEL_HEADER_LEN as usize);
+
+    for wfile in [
+        header.tex_and_audio,
+        header.collision_data,
+        header.model,
+        header.sky,
+        header.mobys,
+        header.portals,
+        header.unk_3,
+        header.unk_4,
+    ] {
+        bytes.extend(wfile.offset.to_le_bytes());
+        bytes.extend(wfile.length.to_le_bytes());
+    }
+
+    for offset in header.moby_offsets {
+        bytes.extend(offset.to_le_bytes());
+    }
 
-    // reverb part
-    copy_limited(&mut file, &mut dst_file, 24 * 1024)?;
+    for id in header.moby_ids {
+        bytes.extend(id.to_le_bytes());
+    }
 
-    let mut a_buffers = Vec::new();
+    bytes
+}
 
-    for i in 0..8 {
-        let lb = i * 64 * 1024;
-        let len = (header.tex_and_audio.length - (lb + 512 * 1024 + 24 * 1024)).min(64 * 1024);
+/// Inverse of read_level, bytes outside every section, sound and gap come
+/// back as zeros
+pub fn write_level(level: &Level) -> anyhow::Result<Vec<u8>> {
+    let header = &level.header;
+
+    let sections = [
+        (
+            "collision_data",
+            header.collision_data,
+            &level.collision_data,
+        ),
+        ("model", header.model, &level.model),
+        ("sky", header.sky, &level.sky),
+        ("mobys", header.mobys, &level.mobys),
+        ("portals", header.portals, &level.portals),
+        ("unk_3", header.unk_3, &level.unk_3),
+        ("unk_4", header.unk_4, &level.unk_4),
+    ];
+
+    for (name, wfile, data) in sections {
+        if data.len() as u64 != wfile.length as u64 {
+            anyhow::bail!(
+                "{name} holds 0x{:x} bytes but the header says 0x{:x}",
+                data.len(),
+                wfile.length
+            );
+        }
+    }
 
-        file.seek(std::io::SeekFrom::Start(
-            header.tex_and_audio.offset as u64 + 512 * 1024 + 24 * 1024 + lb as u64,
-        ))?;
+    if (header.tex_and_audio.length as u64) < TEX_LEN {
+        anyhow::bail!(
+            "0x{:x} bytes of textures and audio can't hold the two texture pages",
+            header.tex_and_audio.length
+        );
+    }
 
-        if len == 0 {
-            break;
+    for (page, data) in level.textures.iter().enumerate() {
+        if data.len() as u64 != TEX_LEN / 2 {
+            anyhow::bail!(
+                "texture page {page} holds 0x{:x} bytes instead of 0x{:x}",
+                data.len(),
+                TEX_LEN / 2
+            );
         }
+    }
 
-        let mut buf = output_dir.clone();
-        buf.push(format!("a_buf_{i}.vag"));
+    let mut file_len = sections
+        .iter()
+        .map(|(_, wfile, _)| *wfile)
+        .chain([header.tex_and_audio])
+        .map(|wfile| wfile.offset as u64 + wfile.length as u64)
+        .fold(LEVEL_HEADER_LEN, u64::max);
 
-        let mut dst_file = File::create(&buf)?;
+    for (offset, gap) in level.gaps.iter() {
+        let end = offset
+            .checked_add(gap.len() as u64)
+            .ok_or_else(|| anyhow::anyhow!("gap at 0x{offset:x} runs past the largest file"))?;
 
-        let mut vag_header = [0u8; 48];
+        file_len = file_len.max(end);
+    }
 
-        vag_header[0..4].copy_from_slice("VAGp".as_bytes());
-        vag_header[4..8].copy_from_slice(&(0x20u32.to_be_bytes()));
-        vag_header[12..16].copy_from_slice(&(len.min(64 * 1024).to_be_bytes()));
-        vag_header[16..20].copy_from_slice(&(11025u32.to_be_bytes()));
+    let file_len = usize::try_from(file_len)?;
+    let mut bytes = vec![0u8; file_len];
 
-        dst_file.write(&vag_header)?;
+    // sections that grew since read_level win over the old gaps
+    for (offset, gap) in level.gaps.iter() {
+        bytes[*offset as usize..][..gap.len()].copy_from_slice(gap);
+    }
 
-        // reverb part
-        copy_limited(&mut file, &mut dst_file, len.min(64 * 1024) as u64)?;
+    bytes[..LEVEL_HEADER_LEN as usize].copy_from_slice(&write_level_header(header));
 
-        a_buffers.push(buf);
+    let tex_and_audio =
+        &mut bytes[header.tex_and_audio.offset as usize..][..header.tex_and_audio.length as usize];
+    let (textures, bank) = tex_and_audio.split_at_mut(TEX_LEN as usize);
 
-        if len < 64 * 1024 {
-            break;
-        }
+    textures.copy_from_slice(&level.textures.concat());
+    write_sound_bank(&level.sound_bank, bank)?;
+
+    for (_, wfile, data) in sections {
+        bytes[wfile.offset as usize..][..wfile.length as usize].copy_from_slice(data);
     }
 
-    let mut make_file = |wfile: WADFile, name: &str| -> anyhow::Result<PathBuf> {
-        file.seek(std::io::SeekFrom::Start(wfile.offset as u64))?;
+    Ok(bytes)
+}
+
+/// Writes every section of `level` into `output_dir`
+pub fn extract_level(
+    level: &Level,
+    provenance: Provenance,
+    output_dir: &Path,
+) -> Result<LevelManifest, LevelError> {
+    create_dir_all(output_dir).map_err(LevelError::io(output_dir))?;
 
-        let mut dst = output_dir.clone();
-        dst.push(name);
+    let write_tex = |name: &str, page: usize| -> Result<PathBuf, LevelError> {
+        let dst = output_dir.join(name);
 
-        let mut dst_file = File::create(&dst)?;
+        let mut dst_file = File::create(&dst).map_err(LevelError::io(&dst))?;
 
-        copy_limited(&mut file, &mut dst_file, wfile.length as u64)?;
+        write_16bpp_tim_header(&mut dst_file, 512, page as u16 * 256, 512, 256)
+            .and_then(|_| dst_file.write_all(&level.textures[page]))
+            .map_err(LevelError::io(&dst))?;
 
         Ok(dst)
     };
 
-    let collision_data = make_file(header.collision_data, "collision_data.bin")?;
-    let model = make_file(header.model, "model.bin")?;
+    let tex_0 = write_tex("tex_0.tim", 0)?;
+    let tex_1 = write_tex("tex_1.tim", 1)?;
 
-    let something = (0..5)
-        .map(|i| make_file(header.something[i], format!("s_{i}.bin").as_str()))
-        .collect::<anyhow::Result<Vec<_>>>()?;
+    let sound_bank = extract_sound_bank(&level.sound_bank, output_dir)?;
+
+    let make_file = |data: &[u8], name: &str| -> Result<PathBuf, LevelError> {
+        let dst = output_dir.join(name);
+
+        std::fs::write(&dst, data).map_err(LevelError::io(&dst))?;
+
+        Ok(dst)
+    };
+
+    let collision_data = make_file(&level.collision_data, "collision_data.bin")?;
+    let model = make_file(&level.model, "model.bin")?;
+
+    let sky = make_file(&level.sky, "sky.bin")?;
+    let mobys = make_file(&level.mobys, "mobys.bin")?;
+    let portals = make_file(&level.portals, "portals.bin")?;
+    let unk_3 = make_file(&level.unk_3, "unk_3.bin")?;
+    let unk_4 = make_file(&level.unk_4, "unk_4.bin")?;
+
+    let moby_models = extract_moby_models(&level.moby_models, output_dir)?;
+
+    let sky_colors = match &level.sky_colors {
+        Some(colors) => {
+            let sky_colors = output_dir.join("sky_colors.json");
+
+            serde_json::to_writer_pretty(
+                File::create(&sky_colors).map_err(LevelError::io(&sky_colors))?,
+                colors,
+            )
+            .map_err(LevelError::json(&sky_colors))?;
+
+            Some(sky_colors)
+        }
+        None => None,
+    };
 
     Ok(LevelManifest {
-        timestamp: Local::now(),
+        schema_version: SCHEMA_VERSION,
+        provenance,
         tex_0,
         tex_1,
-        reverb,
-        audio_buffers: a_buffers,
+        sound_bank,
         collision_data,
         model,
-        something,
-        some_offsets: header.some_offsets.try_into()?,
-        model_indices: header.model_indices.try_into()?,
+        sky,
+        sky_colors,
+        mobys,
+        portals,
+        portal_entries: level.portal_entries.clone(),
+        unk_3,
+        unk_4,
+        moby_models,
     })
 }
+
+/// Extracts a level file into `output_dir`, `provenance` names the disc
+/// image it came from
+pub fn parse_level(
+    level_file: PathBuf,
+    output_dir: PathBuf,
+    provenance: Provenance,
+) -> Result<LevelManifest, LevelError> {
+    let mut file = File::open(&level_file).map_err(LevelError::io(&level_file))?;
+    let level = read_level(&mut BufReader::new(&mut file), &level_file)?;
+
+    extract_level(&level, provenance, &output_dir)
+}
@@ -0,0 +1,373 @@
+use std::{
+    fs::{File, create_dir_all},
+    io::{Read, Seek},
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use wad::{WADFile, read_exact_vec};
+
+use crate::{
+    LevelHeader,
+    error::LevelError,
+    gltf::{GlbBuilder, color, position, triangle_corners},
+    model::{Color, LowPoly, Vertex, read_colors, read_indices},
+    read_i16, read_u16, read_u32,
+};
+
+/// One loaded object model, `data` is relative to the level file
+#[derive(Copy, Clone, Debug)]
+pub struct MobyEntry {
+    pub slot: usize,
+    pub id: u16,
+    pub data: WADFile,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct MobyModel {
+    pub slot: usize,
+    /// Global model ID
+    pub id: u16,
+    pub offset: u32,
+    pub length: u32,
+    pub file: PathBuf,
+}
+
+/// Resolves the level's object model table, empty slots are skipped.
+/// Models are stored back to back inside the mobys section, so each one
+/// ends where the next one starts.
+pub fn moby_table(header: &LevelHeader) -> anyhow::Result<Vec<MobyEntry>> {
+    let section_start = header.mobys.offset;
+    let section_end = header
+        .mobys
+        .offset
+        .checked_add(header.mobys.length)
+        .ok_or_else(|| anyhow::anyhow!("mobys section end overflows"))?;
+
+    let mut offsets = header
+        .moby_offsets
+        .iter()
+        .copied()
+        .filter(|offset| *offset != 0)
+        .collect::<Vec<_>>();
+    offsets.sort();
+    offsets.push(section_end);
+
+    let mut table = Vec::new();
+
+    for (slot, (&offset, &id)) in header
+        .moby_offsets
+        .iter()
+        .zip(header.moby_ids.iter())
+        .enumerate()
+    {
+        if offset == 0 {
+            continue;
+        }
+
+        if offset < section_start || offset >= section_end {
+            anyhow::bail!(
+                "moby slot {slot} offset {offset:#x} is outside the mobys section ({section_start:#x}..{section_end:#x})"
+            );
+        }
+
+        let end = offsets
+            .iter()
+            .copied()
+            .find(|next| *next > offset)
+            .unwrap_or(section_end);
+
+        table.push(MobyEntry {
+            slot,
+            id,
+            data: WADFile {
+                offset,
+                length: end - offset,
+            },
+        });
+    }
+
+    Ok(table)
+}
+
+/// An object model's table entry and data
+#[derive(Clone, Debug)]
+pub struct MobyData {
+    pub entry: MobyEntry,
+    pub data: Vec<u8>,
+}
+
+/// Reads every model of the table
+pub fn read_moby_models(
+    file: &mut (impl Read + Seek),
+    header: &LevelHeader,
+) -> anyhow::Result<Vec<MobyData>> {
+    moby_table(header)?
+        .into_iter()
+        .map(|entry| {
+            file.seek(std::io::SeekFrom::Start(entry.data.offset as u64))?;
+
+            Ok(MobyData {
+                entry,
+                data: read_exact_vec(file, entry.data.length as u64)?,
+            })
+        })
+        .collect()
+}
+
+/// Writes every model into `mobys/model_{slot}_{id}.bin`, slots can share an id
+pub fn extract_moby_models(
+    models: &[MobyData],
+    output_dir: &Path,
+) -> Result<Vec<MobyModel>, LevelError> {
+    let mut mobys_dir = output_dir.to_path_buf();
+    mobys_dir.push("mobys");
+
+    create_dir_all(&mobys_dir).map_err(LevelError::io(&mobys_dir))?;
+
+    let mut extracted = Vec::new();
+
+    for MobyData { entry, data } in models {
+        let mut dst = mobys_dir.clone();
+        dst.push(format!("model_{}_{}.bin", entry.slot, entry.id));
+
+        std::fs::write(&dst, data).map_err(LevelError::io(&dst))?;
+
+        extracted.push(MobyModel {
+            slot: entry.slot,
+            id: entry.id,
+            offset: entry.data.offset,
+            length: entry.data.length,
+            file: dst,
+        });
+    }
+
+    Ok(extracted)
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct AnimationHeader {
+    pub frame_count: u16,
+    /// Frames per second
+    pub frame_rate: u16,
+    pub flags: u16,
+}
+
+#[derive(Debug)]
+pub struct MobyHeader {
+    pub animation: AnimationHeader,
+    pub vertex_count: u16,
+    pub color_count: u16,
+    pub face_count: u16,
+    pub frames_offset: u32,
+    pub colors_offset: u32,
+    pub faces_offset: u32,
+}
+
+#[derive(Debug)]
+pub struct Frame {
+    pub origin: Vertex,
+    pub vertices: Vec<Vertex>,
+}
+
+/// Decoded moby model, every frame has the same vertex count
+#[derive(Debug)]
+pub struct Moby {
+    pub header: MobyHeader,
+    pub frames: Vec<Frame>,
+    pub colors: Vec<Color>,
+    pub faces: Vec<LowPoly>,
+}
+
+fn read_moby_header(file: &mut impl Read) -> anyhow::Result<MobyHeader> {
+    let frame_count = read_u16(file)?;
+    let frame_rate = read_u16(file)?;
+    let flags = read_u16(file)?;
+    let vertex_count = read_u16(file)?;
+    let color_count = read_u16(file)?;
+    let face_count = read_u16(file)?;
+
+    Ok(MobyHeader {
+        animation: AnimationHeader {
+            frame_count,
+            frame_rate,
+            flags,
+        },
+        vertex_count,
+        color_count,
+        face_count,
+        frames_offset: read_u32(file)?,
+        colors_offset: read_u32(file)?,
+        faces_offset: read_u32(file)?,
+    })
+}
+
+fn read_frame(file: &mut impl Read, vertex_count: u16) -> anyhow::Result<Frame> {
+    let origin = Vertex {
+        x: read_i16(file)? as i32,
+        y: read_i16(file)? as i32,
+        z: read_i16(file)? as i32,
+    };
+    // padding
+    read_u16(file)?;
+
+    let vertices = (0..vertex_count)
+        .map(|_| Ok(Vertex::unpack(read_u32(file)?, origin)))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    Ok(Frame { origin, vertices })
+}
+
+/// Decodes an extracted moby model (mobys/model_{slot}_{id}.bin)
+pub fn parse_moby(model_file: PathBuf) -> anyhow::Result<Moby> {
+    let mut file = File::open(&model_file)?;
+
+    let header = read_moby_header(&mut file)?;
+
+    file.seek(std::io::SeekFrom::Start(header.frames_offset as u64))?;
+    let frames = (0..header.animation.frame_count)
+        .map(|_| read_frame(&mut file, header.vertex_count))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    file.seek(std::io::SeekFrom::Start(header.colors_offset as u64))?;
+    let colors = read_colors(&mut file, header.color_count)?;
+
+    file.seek(std::io::SeekFrom::Start(header.faces_offset as u64))?;
+    let faces = (0..header.face_count)
+        .map(|_| {
+            Ok(LowPoly {
+                vertices: read_indices(&mut file)?,
+                colors: read_indices(&mut file)?,
+            })
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    Ok(Moby {
+        header,
+        frames,
+        colors,
+        faces,
+    })
+}
+
+/// Exports a moby as a .glb, frame 0 is the base mesh and every other
+/// frame is a morph target, driven by a step animation over the weights
+pub fn export_moby(model_file: PathBuf, output_file: PathBuf) -> anyhow::Result<()> {
+    let moby = parse_moby(model_file)?;
+
+    let Some(base) = moby.frames.first() else {
+        anyhow::bail!("moby model has no frames");
+    };
+
+    let frame_positions = |frame: &Frame| -> anyhow::Result<Vec<[f32; 3]>> {
+        moby.faces
+            .iter()
+            .flat_map(|face| {
+                triangle_corners(face.is_triangle()).iter().map(|&corner| {
+                    let index = face.vertices[corner];
+
+                    frame
+                        .vertices
+                        .get(index as usize)
+                        .map(position)
+                        .ok_or_else(|| anyhow::anyhow!("vertex {index} out of range"))
+                })
+            })
+            .collect()
+    };
+
+    let colors = moby
+        .faces
+        .iter()
+        .flat_map(|face| {
+            triangle_corners(face.is_triangle()).iter().map(|&corner| {
+                let index = face.colors[corner];
+
+                moby.colors
+                    .get(index as usize)
+                    .map(color)
+                    .ok_or_else(|| anyhow::anyhow!("colour {index} out of range"))
+            })
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let base_positions = frame_positions(base)?;
+
+    let mut builder = GlbBuilder::new();
+    let material = builder.push_color_material();
+
+    let positions = builder.push_accessor(&base_positions, true);
+    let colors = builder.push_accessor(&colors, false);
+
+    // morph targets store the displacement from the base frame
+    let targets = moby.frames[1..]
+        .iter()
+        .map(|frame| {
+            let displacements = frame_positions(frame)?
+                .iter()
+                .zip(base_positions.iter())
+                .map(|(p, b)| [p[0] - b[0], p[1] - b[1], p[2] - b[2]])
+                .collect::<Vec<_>>();
+
+            Ok(json!({ "POSITION": builder.push_accessor(&displacements, true) }))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let target_count = targets.len();
+
+    let mut primitive = json!({
+        "attributes": {
+            "POSITION": positions,
+            "COLOR_0": colors,
+        },
+        "material": material,
+    });
+
+    if target_count > 0 {
+        primitive["targets"] = json!(targets);
+    }
+
+    let mesh = builder.push_mesh(json!({
+        "primitives": [primitive],
+        "weights": vec![0.0; target_count],
+    }));
+
+    let node = builder.push_node(json!({
+        "name": "moby",
+        "mesh": mesh,
+    }));
+
+    if target_count > 0 {
+        let frame_rate = moby.header.animation.frame_rate.max(1) as f32;
+
+        let times = (0..moby.frames.len())
+            .map(|frame| [frame as f32 / frame_rate])
+            .collect::<Vec<_>>();
+
+        // frame 0 has every weight at 0, frame n only enables target n - 1
+        let weights = (0..moby.frames.len())
+            .flat_map(|frame| {
+                (1..=target_count).map(move |target| [if frame == target { 1.0 } else { 0.0 }])
+            })
+            .collect::<Vec<_>>();
+
+        let input = builder.push_accessor(&times, true);
+        let output = builder.push_accessor(&weights, false);
+
+        builder.push_animation(json!({
+            "name": "frames",
+            "samplers": [{
+                "input": input,
+                "output": output,
+                "interpolation": "STEP",
+            }],
+            "channels": [{
+                "sampler": 0,
+                "target": { "node": node, "path": "weights" },
+            }],
+        }));
+    }
+
+    builder.write(output_file)
+}
@@ -0,0 +1,295 @@
+use std::{
+    fs::File,
+    io::{BufReader, Read, Seek},
+    path::PathBuf,
+};
+
+use crate::{read_i16, read_u8, read_u16, read_u32};
+
+/// Texture reference shared by the high LOD polygons of every sector
+#[derive(Copy, Clone, Debug)]
+pub struct TextureRef {
+    /// u, v pairs for the 4 corners
+    pub uvs: [(u8, u8); 4],
+    pub clut: u16,
+    pub tpage: u16,
+}
+
+impl TextureRef {
+    /// VRAM x of the texture page in 16 bit units
+    pub fn page_x(&self) -> u16 {
+        (self.tpage & 0xf) * 64
+    }
+
+    /// VRAM y of the texture page
+    pub fn page_y(&self) -> u16 {
+        ((self.tpage >> 4) & 1) * 256
+    }
+
+    /// 0 = 4bpp, 1 = 8bpp, 2 = 16bpp
+    pub fn color_depth(&self) -> u16 {
+        (self.tpage >> 7) & 3
+    }
+
+    pub fn clut_x(&self) -> u16 {
+        (self.clut & 0x3f) * 16
+    }
+
+    pub fn clut_y(&self) -> u16 {
+        (self.clut >> 6) & 0x1ff
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct BoundingSphere {
+    pub x: i16,
+    pub y: i16,
+    pub z: i16,
+    pub radius: u16,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Vertex {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+}
+
+impl Vertex {
+    // vertices are packed as 11 bit x, 11 bit y, 10 bit z
+    // relative to the sector origin
+    pub(crate) fn unpack(packed: u32, origin: Vertex) -> Self {
+        Self {
+            x: origin.x + ((packed >> 21) & 0x7ff) as i32,
+            y: origin.y + ((packed >> 10) & 0x7ff) as i32,
+            z: origin.z + (packed & 0x3ff) as i32,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct LowPoly {
+    pub vertices: [u8; 4],
+    pub colors: [u8; 4],
+}
+
+impl LowPoly {
+    /// Triangles repeat their first vertex, the real corners are 1, 2 and 3
+    pub fn is_triangle(&self) -> bool {
+        self.vertices[0] == self.vertices[1]
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct HighPoly {
+    pub vertices: [u8; 4],
+    pub colors: [u8; 4],
+    pub texture: u8,
+    pub flags: u8,
+    pub unk_0: u16,
+    pub unk_1: u32,
+}
+
+impl HighPoly {
+    /// Triangles repeat their first vertex, the real corners are 1, 2 and 3
+    pub fn is_triangle(&self) -> bool {
+        self.vertices[0] == self.vertices[1]
+    }
+
+    /// Untextured polygons only use their vertex colours
+    pub fn is_textured(&self) -> bool {
+        self.texture != 0xff
+    }
+}
+
+#[derive(Debug)]
+pub struct SectorHeader {
+    pub bounding: BoundingSphere,
+    pub origin: Vertex,
+    pub lp_vertex_count: u8,
+    pub lp_color_count: u8,
+    pub lp_poly_count: u8,
+    pub hp_vertex_count: u8,
+    pub hp_color_count: u8,
+    pub hp_poly_count: u8,
+    pub flags: u32,
+    pub unk: u32,
+}
+
+#[derive(Debug)]
+pub struct Sector {
+    pub header: SectorHeader,
+    pub lp_vertices: Vec<Vertex>,
+    pub lp_colors: Vec<Color>,
+    pub lp_polys: Vec<LowPoly>,
+    pub hp_vertices: Vec<Vertex>,
+    pub hp_colors: Vec<Color>,
+    pub hp_polys: Vec<HighPoly>,
+}
+
+#[derive(Debug)]
+pub struct Model {
+    pub textures: Vec<TextureRef>,
+    pub sectors: Vec<Sector>,
+}
+
+fn read_texture_ref(file: &mut impl Read) -> anyhow::Result<TextureRef> {
+    let mut uvs = [(0u8, 0u8); 4];
+    for uv in uvs.iter_mut() {
+        *uv = (read_u8(file)?, read_u8(file)?);
+    }
+
+    Ok(TextureRef {
+        uvs,
+        clut: read_u16(file)?,
+        tpage: read_u16(file)?,
+    })
+}
+
+pub(crate) fn read_bounding_sphere(file: &mut impl Read) -> anyhow::Result<BoundingSphere> {
+    Ok(BoundingSphere {
+        x: read_i16(file)?,
+        y: read_i16(file)?,
+        z: read_i16(file)?,
+        radius: read_u16(file)?,
+    })
+}
+
+fn read_sector_header(file: &mut impl Read) -> anyhow::Result<SectorHeader> {
+    let bounding = read_bounding_sphere(file)?;
+
+    let origin = Vertex {
+        x: read_i16(file)? as i32,
+        y: read_i16(file)? as i32,
+        z: read_i16(file)? as i32,
+    };
+    // padding
+    read_u16(file)?;
+
+    let lp_vertex_count = read_u8(file)?;
+    let lp_color_count = read_u8(file)?;
+    let lp_poly_count = read_u8(file)?;
+    read_u8(file)?;
+
+    let hp_vertex_count = read_u8(file)?;
+    let hp_color_count = read_u8(file)?;
+    let hp_poly_count = read_u8(file)?;
+    read_u8(file)?;
+
+    Ok(SectorHeader {
+        bounding,
+        origin,
+        lp_vertex_count,
+        lp_color_count,
+        lp_poly_count,
+        hp_vertex_count,
+        hp_color_count,
+        hp_poly_count,
+        flags: read_u32(file)?,
+        unk: read_u32(file)?,
+    })
+}
+
+fn read_vertices(file: &mut impl Read, count: u8, origin: Vertex) -> anyhow::Result<Vec<Vertex>> {
+    (0..count)
+        .map(|_| Ok(Vertex::unpack(read_u32(file)?, origin)))
+        .collect()
+}
+
+pub(crate) fn read_colors(file: &mut impl Read, count: u16) -> anyhow::Result<Vec<Color>> {
+    (0..count)
+        .map(|_| {
+            Ok(Color {
+                r: read_u8(file)?,
+                g: read_u8(file)?,
+                b: read_u8(file)?,
+                a: read_u8(file)?,
+            })
+        })
+        .collect()
+}
+
+pub(crate) fn read_indices(file: &mut impl Read) -> anyhow::Result<[u8; 4]> {
+    let mut buffer = [0u8; 4];
+    file.read_exact(&mut buffer)?;
+
+    Ok(buffer)
+}
+
+fn read_sector(file: &mut impl Read) -> anyhow::Result<Sector> {
+    let header = read_sector_header(file)?;
+
+    let lp_vertices = read_vertices(file, header.lp_vertex_count, header.origin)?;
+    let lp_colors = read_colors(file, header.lp_color_count as u16)?;
+    let lp_polys = (0..header.lp_poly_count)
+        .map(|_| {
+            Ok(LowPoly {
+                vertices: read_indices(file)?,
+                colors: read_indices(file)?,
+            })
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let hp_vertices = read_vertices(file, header.hp_vertex_count, header.origin)?;
+    let hp_colors = read_colors(file, header.hp_color_count as u16)?;
+    let hp_polys = (0..header.hp_poly_count)
+        .map(|_| {
+            Ok(HighPoly {
+                vertices: read_indices(file)?,
+                colors: read_indices(file)?,
+                texture: read_u8(file)?,
+                flags: read_u8(file)?,
+                unk_0: read_u16(file)?,
+                unk_1: read_u32(file)?,
+            })
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    Ok(Sector {
+        header,
+        lp_vertices,
+        lp_colors,
+        lp_polys,
+        hp_vertices,
+        hp_colors,
+        hp_polys,
+    })
+}
+
+/// Decodes the scenery from a model section, sector offsets count from the
+/// start of `file`
+pub fn read_model(file: &mut (impl Read + Seek)) -> anyhow::Result<Model> {
+    file.rewind()?;
+
+    let texture_count = read_u32(file)?;
+    let textures = (0..texture_count)
+        .map(|_| read_texture_ref(file))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let sector_count = read_u32(file)?;
+    let sector_offsets = (0..sector_count)
+        .map(|_| read_u32(file))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let mut sectors = Vec::new();
+    for offset in sector_offsets {
+        file.seek(std::io::SeekFrom::Start(offset as u64))?;
+
+        sectors.push(read_sector(file)?);
+    }
+
+    Ok(Model { textures, sectors })
+}
+
+/// Decodes the scenery of an extracted model.bin
+pub fn parse_model(model_file: PathBuf) -> anyhow::Result<Model> {
+    read_model(&mut BufReader::new(File::open(&model_file)?))
+}
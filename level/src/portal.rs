@@ -0,0 +1,43 @@
+use std::{fs::File, io::Read, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{read_i16, read_u8, read_u16, read_u32};
+
+/// Warp into another level, triggered inside the sphere
+#[derive(Serialize, Deserialize, Copy, Clone, Debug)]
+pub struct Portal {
+    pub x: i16,
+    pub y: i16,
+    pub z: i16,
+    pub radius: u16,
+    pub destination_level: u8,
+    pub flags: u8,
+    pub unk_0: u16,
+    pub unk_1: u32,
+}
+
+fn read_portal(file: &mut impl Read) -> anyhow::Result<Portal> {
+    Ok(Portal {
+        x: read_i16(file)?,
+        y: read_i16(file)?,
+        z: read_i16(file)?,
+        radius: read_u16(file)?,
+        destination_level: read_u8(file)?,
+        flags: read_u8(file)?,
+        unk_0: read_u16(file)?,
+        unk_1: read_u32(file)?,
+    })
+}
+
+/// Decodes a portal section
+pub fn read_portals(file: &mut impl Read) -> anyhow::Result<Vec<Portal>> {
+    let portal_count = read_u32(file)?;
+
+    (0..portal_count).map(|_| read_portal(file)).collect()
+}
+
+/// Decodes an extracted portal section (portals.bin)
+pub fn parse_portals(portals_file: PathBuf) -> anyhow::Result<Vec<Portal>> {
+    read_portals(&mut File::open(&portals_file)?)
+}
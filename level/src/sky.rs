@@ -0,0 +1,234 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{Read, Seek, Write},
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::{
+    LevelManifest,
+    gltf::{GlbBuilder, color, position, triangle_corners},
+    model::{
+        BoundingSphere, Color, LowPoly, Vertex, read_bounding_sphere, read_colors, read_indices,
+    },
+    read_i16, read_level_header, read_u8, read_u16, read_u32,
+};
+
+#[derive(Debug)]
+pub struct SkySector {
+    pub bounding: BoundingSphere,
+    pub vertices: Vec<Vertex>,
+    /// Offset of the colour table from the start of the sky section
+    pub colors_offset: u32,
+    pub colors: Vec<Color>,
+    pub polys: Vec<LowPoly>,
+}
+
+#[derive(Debug)]
+pub struct Sky {
+    pub background: Color,
+    pub sectors: Vec<SkySector>,
+}
+
+/// Editable sky colours, written back into the level on Repack
+#[derive(Serialize, Deserialize)]
+pub struct SkyColors {
+    pub background: [u8; 3],
+    pub sectors: Vec<Vec<[u8; 4]>>,
+}
+
+impl Sky {
+    pub fn colors(&self) -> SkyColors {
+        SkyColors {
+            background: [self.background.r, self.background.g, self.background.b],
+            sectors: self
+                .sectors
+                .iter()
+                .map(|sector| sector.colors.iter().map(|c| [c.r, c.g, c.b, c.a]).collect())
+                .collect(),
+        }
+    }
+}
+
+fn read_sky_sector(file: &mut (impl Read + Seek), section_start: u64) -> anyhow::Result<SkySector> {
+    let bounding = read_bounding_sphere(file)?;
+
+    let vertex_count = read_u16(file)?;
+    let color_count = read_u16(file)?;
+    let poly_count = read_u16(file)?;
+    read_u16(file)?;
+
+    let vertices = (0..vertex_count)
+        .map(|_| {
+            let vertex = Vertex {
+                x: read_i16(file)? as i32,
+                y: read_i16(file)? as i32,
+                z: read_i16(file)? as i32,
+            };
+            // padding
+            read_u16(file)?;
+
+            Ok(vertex)
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let colors_offset = (file.stream_position()? - section_start) as u32;
+    let colors = read_colors(file, color_count)?;
+
+    let polys = (0..poly_count)
+        .map(|_| {
+            Ok(LowPoly {
+                vertices: read_indices(file)?,
+                colors: read_indices(file)?,
+            })
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    Ok(SkySector {
+        bounding,
+        vertices,
+        colors_offset,
+        colors,
+        polys,
+    })
+}
+
+/// Decodes the sky section starting at `section_start`
+pub fn read_sky(file: &mut (impl Read + Seek), section_start: u64) -> anyhow::Result<Sky> {
+    file.seek(std::io::SeekFrom::Start(section_start))?;
+
+    let background = Color {
+        r: read_u8(file)?,
+        g: read_u8(file)?,
+        b: read_u8(file)?,
+        a: read_u8(file)?,
+    };
+
+    let sector_count = read_u32(file)?;
+    let sector_offsets = (0..sector_count)
+        .map(|_| read_u32(file))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let mut sectors = Vec::new();
+    for offset in sector_offsets {
+        file.seek(std::io::SeekFrom::Start(section_start + offset as u64))?;
+
+        sectors.push(read_sky_sector(file, section_start)?);
+    }
+
+    Ok(Sky {
+        background,
+        sectors,
+    })
+}
+
+/// Decodes an extracted sky section (sky.bin)
+pub fn parse_sky(sky_file: PathBuf) -> anyhow::Result<Sky> {
+    let mut file = File::open(&sky_file)?;
+
+    read_sky(&mut file, 0)
+}
+
+/// Exports the sky as a vertex coloured .glb, one node per sector
+pub fn export_sky(level_manifest: &LevelManifest, output_file: PathBuf) -> anyhow::Result<()> {
+    let sky = parse_sky(level_manifest.sky.clone())?;
+
+    let mut builder = GlbBuilder::new();
+    let material = builder.push_color_material();
+
+    for (i, sector) in sky.sectors.iter().enumerate() {
+        if sector.polys.is_empty() {
+            continue;
+        }
+
+        let mut positions = Vec::new();
+        let mut colors = Vec::new();
+
+        for poly in sector.polys.iter() {
+            for &corner in triangle_corners(poly.is_triangle()) {
+                let index = poly.vertices[corner];
+                let Some(vertex) = sector.vertices.get(index as usize) else {
+                    anyhow::bail!("sky sector {i}: vertex {index} out of range");
+                };
+
+                let index = poly.colors[corner];
+                let Some(vertex_color) = sector.colors.get(index as usize) else {
+                    anyhow::bail!("sky sector {i}: colour {index} out of range");
+                };
+
+                positions.push(position(vertex));
+                colors.push(color(vertex_color));
+            }
+        }
+
+        let positions = builder.push_accessor(&positions, true);
+        let colors = builder.push_accessor(&colors, false);
+
+        let mesh = builder.push_mesh(json!({
+            "primitives": [{
+                "attributes": {
+                    "POSITION": positions,
+                    "COLOR_0": colors,
+                },
+                "material": material,
+            }],
+        }));
+
+        builder.push_node(json!({
+            "name": format!("sky_{i}"),
+            "mesh": mesh,
+        }));
+    }
+
+    builder.write(output_file)
+}
+
+/// Writes edited sky colours back into the level file in place
+pub fn import_sky_colors(level_file: PathBuf, colors_file: &Path) -> anyhow::Result<()> {
+    let colors: SkyColors = serde_json::from_reader(File::open(colors_file)?)?;
+
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(&level_file)?;
+
+    let header = read_level_header(&mut file)?;
+    let section_start = header.sky.offset as u64;
+
+    let sky = read_sky(&mut file, section_start)?;
+
+    if colors.sectors.len() != sky.sectors.len() {
+        anyhow::bail!(
+            "{}: expected {} sky sectors, got {}",
+            colors_file.display(),
+            sky.sectors.len(),
+            colors.sectors.len()
+        );
+    }
+
+    file.seek(std::io::SeekFrom::Start(section_start))?;
+    file.write_all(&colors.background)?;
+
+    for (i, (sector, sector_colors)) in sky.sectors.iter().zip(colors.sectors).enumerate() {
+        if sector_colors.len() != sector.colors.len() {
+            anyhow::bail!(
+                "{}: sky sector {i} has {} colours, got {}",
+                colors_file.display(),
+                sector.colors.len(),
+                sector_colors.len()
+            );
+        }
+
+        file.seek(std::io::SeekFrom::Start(
+            section_start + sector.colors_offset as u64,
+        ))?;
+
+        for c in sector_colors {
+            file.write_all(&c)?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,264 @@
+use std::{
+    fs::File,
+    io::{self, Read, Seek, Write},
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use wad::read_exact_vec;
+
+use crate::error::LevelError;
+
+/// SPU pitch value that plays a sample back at 44100 Hz
+const SPU_BASE_PITCH: u32 = 0x1000;
+
+#[derive(Copy, Clone, Debug)]
+pub struct SoundEntry {
+    pub spu_address: u32,
+    pub size: u32,
+    pub pitch: u16,
+    pub id: u16,
+}
+
+impl SoundEntry {
+    pub fn sample_rate(&self) -> u32 {
+        self.pitch as u32 * 44100 / SPU_BASE_PITCH
+    }
+}
+
+/// Sits right after the 512 KiB of VRAM data: sound count, SPU base
+/// address, then 12 bytes per sound (SPU address, size, pitch, id). Sample
+/// data follows the entries at `spu_address - spu_base`.
+///
+/// This layout is reverse engineered and unconfirmed, read_sound_bank
+/// rejects banks whose entries or samples don't fit the section. There is
+/// no separate reverb region: the 24 KiB the old extract called reverb
+/// starts with this header, the rest of it is sample data. Bytes no sound
+/// claims are kept as level gaps and written back on repack.
+#[derive(Debug)]
+pub struct SoundBankHeader {
+    pub sound_count: u32,
+    pub spu_base: u32,
+    pub entries: Vec<SoundEntry>,
+}
+
+impl SoundBankHeader {
+    /// Size of the header in the level file, sample data follows it
+    pub fn size(&self) -> u64 {
+        8 + self.sound_count as u64 * 12
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Sound {
+    pub id: u16,
+    pub spu_address: u32,
+    pub size: u32,
+    pub pitch: u16,
+    pub sample_rate: u32,
+    pub file: PathBuf,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SoundBank {
+    pub spu_base: u32,
+    pub sounds: Vec<Sound>,
+}
+
+pub fn read_sound_bank_header(file: &mut impl Read) -> anyhow::Result<SoundBankHeader> {
+    let mut buffer_0 = [0u8; 4];
+    let mut buffer_1 = [0u8; 4];
+
+    file.read_exact(&mut buffer_0)?;
+    file.read_exact(&mut buffer_1)?;
+
+    let mut header = SoundBankHeader {
+        sound_count: u32::from_le_bytes(buffer_0),
+        spu_base: u32::from_le_bytes(buffer_1),
+        entries: Vec::new(),
+    };
+
+    let mut short_0 = [0u8; 2];
+    let mut short_1 = [0u8; 2];
+    for _ in 0..header.sound_count {
+        file.read_exact(&mut buffer_0)?;
+        file.read_exact(&mut buffer_1)?;
+        file.read_exact(&mut short_0)?;
+        file.read_exact(&mut short_1)?;
+
+        header.entries.push(SoundEntry {
+            spu_address: u32::from_le_bytes(buffer_0),
+            size: u32::from_le_bytes(buffer_1),
+            pitch: u16::from_le_bytes(short_0),
+            id: u16::from_le_bytes(short_1),
+        });
+    }
+
+    Ok(header)
+}
+
+fn write_vag_header(dst_file: &mut File, size: u32, sample_rate: u32) -> io::Result<()> {
+    let mut vag_header = [0u8; 48];
+
+    vag_header[0..4].copy_from_slice("VAGp".as_bytes());
+    vag_header[4..8].copy_from_slice(&(0x20u32.to_be_bytes()));
+    vag_header[12..16].copy_from_slice(&(size.to_be_bytes()));
+    vag_header[16..20].copy_from_slice(&(sample_rate.to_be_bytes()));
+
+    dst_file.write_all(&vag_header)?;
+
+    Ok(())
+}
+
+/// A sound of the bank and its ADPCM sample data
+#[derive(Clone, Debug)]
+pub struct SoundSample {
+    pub entry: SoundEntry,
+    pub data: Vec<u8>,
+}
+
+/// Sound bank as stored in the level, in header order
+#[derive(Clone, Debug)]
+pub struct SoundBankData {
+    pub spu_base: u32,
+    pub samples: Vec<SoundSample>,
+}
+
+/// Reads the bank starting at `bank_offset`, the bank and its samples must
+/// end before `bank_end`
+pub fn read_sound_bank(
+    file: &mut (impl Read + Seek),
+    bank_offset: u64,
+    bank_end: u64,
+) -> anyhow::Result<SoundBankData> {
+    file.seek(std::io::SeekFrom::Start(bank_offset))?;
+
+    let header = read_sound_bank_header(file)?;
+
+    let data_offset = bank_offset + header.size();
+
+    if data_offset > bank_end {
+        anyhow::bail!(
+            "{} sound entries run past the end of the section at 0x{bank_end:x}",
+            header.sound_count
+        );
+    }
+
+    let mut samples = Vec::new();
+
+    for entry in header.entries.iter() {
+        // unused entries, their address isn't checked
+        if entry.size == 0 {
+            samples.push(SoundSample {
+                entry: *entry,
+                data: Vec::new(),
+            });
+            continue;
+        }
+
+        let sample_offset = entry
+            .spu_address
+            .checked_sub(header.spu_base)
+            .map(|relative| data_offset + relative as u64)
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "sound {} SPU address 0x{:x} is below the bank base 0x{:x}",
+                    entry.id,
+                    entry.spu_address,
+                    header.spu_base
+                )
+            })?;
+
+        if sample_offset + entry.size as u64 > bank_end {
+            anyhow::bail!(
+                "sound {} (0x{:x} bytes at 0x{sample_offset:x}) runs past the end of the section at 0x{bank_end:x}",
+                entry.id,
+                entry.size
+            );
+        }
+
+        file.seek(std::io::SeekFrom::Start(sample_offset))?;
+
+        samples.push(SoundSample {
+            entry: *entry,
+            data: read_exact_vec(file, entry.size as u64)?,
+        });
+    }
+
+    Ok(SoundBankData {
+        spu_base: header.spu_base,
+        samples,
+    })
+}
+
+/// Writes the bank header and samples into `dst`, which starts at the bank
+pub fn write_sound_bank(bank: &SoundBankData, dst: &mut [u8]) -> anyhow::Result<()> {
+    let header_size = 8 + bank.samples.len() * 12;
+
+    let mut header = Vec::with_capacity(header_size);
+    header.extend((bank.samples.len() as u32).to_le_bytes());
+    header.extend(bank.spu_base.to_le_bytes());
+
+    for SoundSample { entry, .. } in bank.samples.iter() {
+        header.extend(entry.spu_address.to_le_bytes());
+        header.extend(entry.size.to_le_bytes());
+        header.extend(entry.pitch.to_le_bytes());
+        header.extend(entry.id.to_le_bytes());
+    }
+
+    dst.get_mut(..header_size)
+        .ok_or_else(|| anyhow::anyhow!("{} sound entries don't fit", bank.samples.len()))?
+        .copy_from_slice(&header);
+
+    for SoundSample { entry, data } in bank.samples.iter() {
+        if data.is_empty() {
+            continue;
+        }
+
+        let start = header_size + entry.spu_address.wrapping_sub(bank.spu_base) as usize;
+
+        dst.get_mut(start..start + data.len())
+            .ok_or_else(|| anyhow::anyhow!("sound {} doesn't fit in the bank", entry.id))?
+            .copy_from_slice(data);
+    }
+
+    Ok(())
+}
+
+/// Writes every sound of the bank into its own .vag
+pub fn extract_sound_bank(
+    bank: &SoundBankData,
+    output_dir: &Path,
+) -> Result<SoundBank, LevelError> {
+    let mut sounds = Vec::new();
+
+    for SoundSample { entry, data } in bank.samples.iter() {
+        if entry.size == 0 {
+            continue;
+        }
+
+        let mut dst = output_dir.to_path_buf();
+        dst.push(format!("sound_{}.vag", entry.id));
+
+        let mut dst_file = File::create(&dst).map_err(LevelError::io(&dst))?;
+
+        write_vag_header(&mut dst_file, entry.size, entry.sample_rate())
+            .and_then(|_| dst_file.write_all(data))
+            .map_err(LevelError::io(&dst))?;
+
+        sounds.push(Sound {
+            id: entry.id,
+            spu_address: entry.spu_address,
+            size: entry.size,
+            pitch: entry.pitch,
+            sample_rate: entry.sample_rate(),
+            file: dst,
+        });
+    }
+
+    Ok(SoundBank {
+        spu_base: bank.spu_base,
+        sounds,
+    })
+}
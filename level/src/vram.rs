@@ -0,0 +1,164 @@
+use std::{
+    fs::File,
+    io::{Read, Seek},
+    path::Path,
+};
+
+use crate::model::TextureRef;
+
+pub const VRAM_WIDTH: usize = 1024;
+pub const VRAM_HEIGHT: usize = 512;
+
+// 20 byte header written by write_16bpp_tim_header
+const TIM_HEADER_LEN: u64 = 20;
+
+/// 16 bit view of the PS1 VRAM
+pub struct Vram {
+    pub data: Vec<u16>,
+}
+
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub rgba: Vec<u8>,
+}
+
+impl Image {
+    pub fn to_png(&self) -> anyhow::Result<Vec<u8>> {
+        let mut png_data = Vec::new();
+
+        let mut encoder = png::Encoder::new(&mut png_data, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.rgba)?;
+        writer.finish()?;
+
+        Ok(png_data)
+    }
+}
+
+// 5 bit per channel, black is fully transparent
+fn to_rgba(color: u16) -> [u8; 4] {
+    let r = (color & 0x1f) as u8;
+    let g = ((color >> 5) & 0x1f) as u8;
+    let b = ((color >> 10) & 0x1f) as u8;
+    let a = if color == 0 { 0 } else { 0xff };
+
+    [r << 3 | r >> 2, g << 3 | g >> 2, b << 3 | b >> 2, a]
+}
+
+impl Vram {
+    pub fn new() -> Self {
+        Self {
+            data: vec![0; VRAM_WIDTH * VRAM_HEIGHT],
+        }
+    }
+
+    /// Loads both extracted level pages (tex_0.tim and tex_1.tim)
+    pub fn from_level(tex_0: &Path, tex_1: &Path) -> anyhow::Result<Self> {
+        let mut vram = Self::new();
+
+        vram.load_tim(tex_0, 512, 0, 512, 256)?;
+        vram.load_tim(tex_1, 512, 256, 512, 256)?;
+
+        Ok(vram)
+    }
+
+    fn load_tim(
+        &mut self,
+        tim: &Path,
+        x: usize,
+        y: usize,
+        w: usize,
+        h: usize,
+    ) -> anyhow::Result<()> {
+        let mut file = File::open(tim)?;
+        file.seek(std::io::SeekFrom::Start(TIM_HEADER_LEN))?;
+
+        let mut row = vec![0u8; w * 2];
+        for line in 0..h {
+            file.read_exact(&mut row)?;
+
+            let start = (y + line) * VRAM_WIDTH + x;
+            for (i, pixel) in row.chunks_exact(2).enumerate() {
+                self.data[start + i] = u16::from_le_bytes([pixel[0], pixel[1]]);
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> u16 {
+        self.data[(y % VRAM_HEIGHT) * VRAM_WIDTH + (x % VRAM_WIDTH)]
+    }
+
+    /// Reads the texel at u, v of a texture page, resolving the CLUT
+    pub fn texel(&self, texture: &TextureRef, u: u8, v: u8) -> u16 {
+        let x = texture.page_x() as usize;
+        let y = texture.page_y() as usize + v as usize;
+
+        match texture.color_depth() {
+            0 => {
+                let word = self.get(x + u as usize / 4, y);
+                let index = (word >> ((u % 4) * 4)) & 0xf;
+
+                self.get(
+                    texture.clut_x() as usize + index as usize,
+                    texture.clut_y() as usize,
+                )
+            }
+            1 => {
+                let word = self.get(x + u as usize / 2, y);
+                let index = (word >> ((u % 2) * 8)) & 0xff;
+
+                self.get(
+                    texture.clut_x() as usize + index as usize,
+                    texture.clut_y() as usize,
+                )
+            }
+            _ => self.get(x + u as usize, y),
+        }
+    }
+
+    /// Cuts out the area covered by the texture's uvs
+    pub fn texture_image(&self, texture: &TextureRef) -> Image {
+        let (u0, v0, u1, v1) = texture_bounds(texture);
+
+        let width = (u1 - u0) as u32 + 1;
+        let height = (v1 - v0) as u32 + 1;
+
+        let mut rgba = Vec::with_capacity((width * height * 4) as usize);
+        for v in v0..=v1 {
+            for u in u0..=u1 {
+                rgba.extend_from_slice(&to_rgba(self.texel(texture, u, v)));
+            }
+        }
+
+        Image {
+            width,
+            height,
+            rgba,
+        }
+    }
+}
+
+impl Default for Vram {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns (min u, min v, max u, max v) of the texture's corners
+pub fn texture_bounds(texture: &TextureRef) -> (u8, u8, u8, u8) {
+    let us = texture.uvs.iter().map(|(u, _)| *u);
+    let vs = texture.uvs.iter().map(|(_, v)| *v);
+
+    (
+        us.clone().min().unwrap_or(0),
+        vs.clone().min().unwrap_or(0),
+        us.max().unwrap_or(0),
+        vs.max().unwrap_or(0),
+    )
+}
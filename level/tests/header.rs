@@ -0,0 +1,42 @@
+use std::{io::Cursor, path::Path};
+
+use level::{error::LevelError, read_level};
+use proptest::prelude::*;
+
+fn set_u32(bytes: &mut [u8], offset: usize, value: u32) {
+    bytes[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+}
+
+fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+fn invalid_section(bytes: &[u8]) -> Option<String> {
+    match read_level(&mut Cursor::new(bytes), Path::new("level.dat")) {
+        Err(LevelError::Invalid { section, .. }) => Some(section),
+        _ => None,
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(16))]
+
+    #[test]
+    fn overlapping_sections_are_rejected(mut bytes in fixtures::level::level()) {
+        // collision data starting inside the textures
+        let tex_offset = u32_at(&bytes, 0);
+        set_u32(&mut bytes, 8, tex_offset + 0x800);
+        set_u32(&mut bytes, 12, 0x800);
+
+        prop_assert_eq!(invalid_section(&bytes), Some("collision_data".into()));
+    }
+
+    #[test]
+    fn moby_offsets_outside_their_section_are_rejected(mut bytes in fixtures::level::level(), slot in 0..64usize) {
+        // a model slot pointing at the textures
+        let tex_offset = u32_at(&bytes, 0);
+        set_u32(&mut bytes, 64 + slot * 4, tex_offset);
+
+        prop_assert_eq!(invalid_section(&bytes), Some("mobys".into()));
+    }
+}
@@ -0,0 +1,91 @@
+use std::fs;
+
+use level::inject::{TextureRect, replace_sound, replace_texture_page};
+use proptest::prelude::*;
+
+/// A VAG header at 22050 Hz followed by `samples`
+fn vag(samples: &[u8]) -> Vec<u8> {
+    let mut vag = vec![0u8; 48];
+    vag[..4].copy_from_slice(b"VAGp");
+    vag[16..20].copy_from_slice(&22050u32.to_be_bytes());
+    vag.extend_from_slice(samples);
+
+    vag
+}
+
+fn rect() -> impl Strategy<Value = TextureRect> {
+    (0..512u16, 0..256u16)
+        .prop_flat_map(|(x, y)| (Just(x), Just(y), 0..=512 - x, 0..=256 - y))
+        .prop_map(|(x, y, width, height)| TextureRect {
+            x,
+            y,
+            width,
+            height,
+        })
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(32))]
+
+    #[test]
+    fn replace_texture_page_only_writes_the_rect(
+        bytes in fixtures::level::level(),
+        page in 0..2u8,
+        rect in rect(),
+    ) {
+        let dir = tempfile::tempdir().unwrap();
+        let level_file = dir.path().join("level.dat");
+        fs::write(&level_file, &bytes).unwrap();
+
+        // 0xff pixels behind a 20 byte header
+        let tim_file = dir.path().join("tex.tim");
+        let mut tim = vec![0u8; 20];
+        tim.extend(vec![0xffu8; 256 * 1024]);
+        fs::write(&tim_file, &tim).unwrap();
+
+        replace_texture_page(level_file.clone(), page, &tim_file, rect).unwrap();
+
+        let after = fs::read(&level_file).unwrap();
+        let page_offset =
+            u32::from_le_bytes(bytes[0..4].try_into().unwrap()) as usize + page as usize * 256 * 1024;
+
+        for (offset, (before, after)) in bytes.iter().zip(after.iter()).enumerate() {
+            let pixel = offset
+                .checked_sub(page_offset)
+                .filter(|pixel| *pixel < 256 * 1024)
+                .map(|pixel| ((pixel % 1024 / 2) as u16, (pixel / 1024) as u16));
+            let inside = pixel.is_some_and(|(x, y)| {
+                (rect.x..rect.x + rect.width).contains(&x) && (rect.y..rect.y + rect.height).contains(&y)
+            });
+
+            prop_assert_eq!(*after, if inside { 0xff } else { *before });
+        }
+    }
+
+    #[test]
+    fn replace_sound_writes_all_or_nothing(bytes in fixtures::level::level(), fill: u8) {
+        let dir = tempfile::tempdir().unwrap();
+        let level_file = dir.path().join("level.dat");
+        fs::write(&level_file, &bytes).unwrap();
+
+        // the bank follows the two texture pages
+        let u32_at = |offset: usize| u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap());
+        let bank = u32_at(0) as usize + 512 * 1024;
+
+        for entry in 0..u32_at(bank) as usize {
+            let entry = bank + 8 + entry * 12;
+            let size = u32_at(entry + 4);
+            let id = u16::from_le_bytes([bytes[entry + 10], bytes[entry + 11]]);
+
+            let before = fs::read(&level_file).unwrap();
+
+            let vag_file = dir.path().join("sound.vag");
+            fs::write(&vag_file, vag(&vec![fill; size as usize])).unwrap();
+
+            // unused entries point anywhere, those have to be refused
+            if replace_sound(level_file.clone(), id, &vag_file).is_err() {
+                prop_assert!(fs::read(&level_file).unwrap() == before);
+            }
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use std::fs;
+
+use level::{LevelManifest, SCHEMA_VERSION};
+use serde_json::json;
+
+#[test]
+fn loads_a_level_json_from_before_versioning() {
+    let dir = tempfile::tempdir().unwrap();
+    let level_dir = dir.path().join("level_10_summer_forest_data");
+    fs::create_dir_all(&level_dir).unwrap();
+
+    let legacy = "extract/WAD/levels/level_10_summer_forest_data";
+
+    // sky background and no sectors, no portals
+    fs::write(level_dir.join("s_0.bin"), [1, 2, 3, 0, 0, 0, 0, 0]).unwrap();
+    fs::write(level_dir.join("s_2.bin"), [0, 0, 0, 0]).unwrap();
+
+    let level_json = level_dir.join("level.json");
+    fs::write(
+        &level_json,
+        serde_json::to_string(&json!({
+            "timestamp": "2025-03-01T12:00:00.000000+01:00",
+            "tex_0": format!("{legacy}/tex_0.tim"),
+            "tex_1": format!("{legacy}/tex_1.tim"),
+            "reverb": format!("{legacy}/a_reverb.bin"),
+            "audio_buffers": [format!("{legacy}/a_buf_0.vag")],
+            "collision_data": format!("{legacy}/collision_data.bin"),
+            "model": format!("{legacy}/model.bin"),
+            "something": (0..5).map(|i| format!("{legacy}/s_{i}.bin")).collect::<Vec<_>>(),
+            "some_offsets": vec![0u32; 64],
+            "model_indices": vec![0u16; 64],
+        }))
+        .unwrap(),
+    )
+    .unwrap();
+
+    let manifest = LevelManifest::load(&level_json, dir.path()).unwrap();
+
+    assert_eq!(manifest.schema_version, SCHEMA_VERSION);
+    assert_eq!(manifest.tex_0, level_dir.join("tex_0.tim"));
+    assert_eq!(manifest.sky, level_dir.join("s_0.bin"));
+    assert_eq!(manifest.mobys, level_dir.join("s_1.bin"));
+    assert_eq!(manifest.portals, level_dir.join("s_2.bin"));
+    assert_eq!(manifest.unk_4, level_dir.join("s_4.bin"));
+    assert!(manifest.portal_entries.as_ref().unwrap().is_empty());
+    assert!(manifest.sound_bank.sounds.is_empty());
+    assert!(manifest.provenance.source_sha256.is_empty());
+
+    // loading leaves the directory alone, the sky is passed through
+    assert!(manifest.sky_colors.is_none());
+    assert!(!level_dir.join("sky_colors.json").exists());
+
+    // saving writes the current version, loading it again changes nothing
+    manifest.save(&level_json, dir.path()).unwrap();
+    let reloaded = LevelManifest::load(&level_json, dir.path()).unwrap();
+    assert_eq!(reloaded.sky, manifest.sky);
+    assert!(reloaded.sky_colors.is_none());
+}
+
+#[test]
+fn rejects_manifests_from_newer_versions() {
+    let dir = tempfile::tempdir().unwrap();
+    let level_json = dir.path().join("level.json");
+    fs::write(&level_json, r#"{ "schema_version": 99 }"#).unwrap();
+
+    let error = LevelManifest::load(&level_json, dir.path()).err().unwrap();
+
+    assert!(error.to_string().contains("update s2"));
+}
@@ -0,0 +1,49 @@
+use std::io::Cursor;
+
+use level::model::read_model;
+use proptest::prelude::*;
+
+fn u32_at(bytes: &[u8], offset: usize) -> usize {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap()) as usize
+}
+
+proptest! {
+    #[test]
+    fn reads_every_sector_with_indices_in_range(bytes in fixtures::model::model()) {
+        let model = read_model(&mut Cursor::new(&bytes)).unwrap();
+
+        let texture_count = u32_at(&bytes, 0);
+        let sector_table = 4 + texture_count * 12;
+
+        prop_assert_eq!(model.textures.len(), texture_count);
+        prop_assert_eq!(model.sectors.len(), u32_at(&bytes, sector_table));
+
+        for (i, sector) in model.sectors.iter().enumerate() {
+            // counts follow the bounding sphere, origin and padding
+            let counts = u32_at(&bytes, sector_table + 4 + i * 4) + 16;
+            let count = |offset: usize| bytes[counts + offset] as usize;
+
+            prop_assert_eq!(sector.lp_vertices.len(), count(0));
+            prop_assert_eq!(sector.lp_colors.len(), count(1));
+            prop_assert_eq!(sector.lp_polys.len(), count(2));
+            prop_assert_eq!(sector.hp_vertices.len(), count(4));
+            prop_assert_eq!(sector.hp_colors.len(), count(5));
+            prop_assert_eq!(sector.hp_polys.len(), count(6));
+
+            for poly in sector.lp_polys.iter() {
+                prop_assert!(poly.vertices.iter().all(|&v| (v as usize) < sector.lp_vertices.len()));
+                prop_assert!(poly.colors.iter().all(|&c| (c as usize) < sector.lp_colors.len()));
+            }
+
+            for poly in sector.hp_polys.iter() {
+                prop_assert!(poly.vertices.iter().all(|&v| (v as usize) < sector.hp_vertices.len()));
+                prop_assert!(poly.colors.iter().all(|&c| (c as usize) < sector.hp_colors.len()));
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_truncated_models(bytes in fixtures::model::model()) {
+        prop_assert!(read_model(&mut Cursor::new(&bytes[..bytes.len() - 1])).is_err());
+    }
+}
@@ -0,0 +1,154 @@
+use std::{fs, io::Cursor, path::Path};
+
+use level::{
+    parse_level, read_level,
+    sky::{export_sky, parse_sky},
+    write_level,
+};
+use proptest::prelude::*;
+use wad::Provenance;
+
+proptest! {
+    // every case is over half a megabyte
+    #![proptest_config(ProptestConfig::with_cases(32))]
+
+    #[test]
+    fn read_then_write_is_identical(bytes in fixtures::level::level()) {
+        let level = read_level(&mut Cursor::new(&bytes), Path::new("level.dat")).unwrap();
+
+        prop_assert_eq!(write_level(&level).unwrap(), bytes);
+    }
+
+    #[test]
+    fn parse_output_rebuilds_the_level(bytes in fixtures::level::level()) {
+        let dir = tempfile::tempdir().unwrap();
+        let level_file = dir.path().join("level.dat");
+        fs::write(&level_file, &bytes).unwrap();
+
+        let manifest = parse_level(level_file, dir.path().join("level"), Provenance::default()).unwrap();
+        let mut level = read_level(&mut Cursor::new(&bytes), Path::new("level.dat")).unwrap();
+
+        // swap in what was extracted, minus the TIM and VAG headers
+        let page = |tim: &Path| {
+            let tim = fs::read(tim).unwrap();
+
+            tim[tim.len() - 512 * 256 * 2..].to_vec()
+        };
+        level.textures = [page(&manifest.tex_0), page(&manifest.tex_1)];
+
+        for sample in level.sound_bank.samples.iter_mut() {
+            if let Some(sound) = manifest.sound_bank.sounds.iter().find(|sound| sound.id == sample.entry.id) {
+                sample.data = fs::read(&sound.file).unwrap()[48..].to_vec();
+            }
+        }
+
+        level.collision_data = fs::read(&manifest.collision_data).unwrap();
+        level.model = fs::read(&manifest.model).unwrap();
+        level.sky = fs::read(&manifest.sky).unwrap();
+        level.mobys = fs::read(&manifest.mobys).unwrap();
+        level.portals = fs::read(&manifest.portals).unwrap();
+        level.unk_3 = fs::read(&manifest.unk_3).unwrap();
+        level.unk_4 = fs::read(&manifest.unk_4).unwrap();
+
+        prop_assert_eq!(write_level(&level).unwrap(), bytes);
+    }
+
+    #[test]
+    fn write_rejects_inconsistent_levels(bytes in fixtures::level::level(), edit in 0..4usize) {
+        let mut level = read_level(&mut Cursor::new(&bytes), Path::new("level.dat")).unwrap();
+
+        match edit {
+            0 => level.model.push(0),
+            1 => {
+                level.textures[1].pop();
+            }
+            2 => level.header.tex_and_audio.length = 0x100,
+            _ => level.gaps.push((u64::MAX, vec![1])),
+        }
+
+        prop_assert!(write_level(&level).is_err());
+    }
+
+    #[test]
+    fn undecodable_sky_and_portals_pass_through(mut bytes in fixtures::level::level()) {
+        let section = |bytes: &[u8], index: usize| {
+            u32::from_le_bytes(bytes[index * 8..index * 8 + 4].try_into().unwrap()) as usize
+        };
+
+        // sector and portal counts far past the end of their sections
+        let sky = section(&bytes, 3);
+        let portals = section(&bytes, 5);
+        bytes[sky + 4..sky + 8].copy_from_slice(&u32::MAX.to_le_bytes());
+        bytes[portals..portals + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+
+        let level = read_level(&mut Cursor::new(&bytes), Path::new("level.dat")).unwrap();
+
+        prop_assert!(level.sky_colors.is_none());
+        prop_assert!(level.portal_entries.is_none());
+        prop_assert_eq!(write_level(&level).unwrap(), bytes.clone());
+
+        let dir = tempfile::tempdir().unwrap();
+        let level_file = dir.path().join("level.dat");
+        fs::write(&level_file, &bytes).unwrap();
+
+        let manifest = parse_level(level_file, dir.path().join("level"), Provenance::default()).unwrap();
+
+        prop_assert!(manifest.sky_colors.is_none());
+        prop_assert_eq!(fs::read(manifest.sky).unwrap(), &bytes[sky..sky + level.sky.len()]);
+    }
+
+    #[test]
+    fn parse_extracts_every_sound(bytes in fixtures::level::level()) {
+        let dir = tempfile::tempdir().unwrap();
+        let level_file = dir.path().join("level.dat");
+        fs::write(&level_file, &bytes).unwrap();
+
+        let manifest = parse_level(level_file, dir.path().join("level"), Provenance::default()).unwrap();
+
+        for sound in manifest.sound_bank.sounds {
+            prop_assert_eq!(fs::metadata(sound.file).unwrap().len(), 48 + sound.size as u64);
+        }
+    }
+
+    #[test]
+    fn parse_extracts_every_moby_model(bytes in fixtures::level::level()) {
+        let dir = tempfile::tempdir().unwrap();
+        let level_file = dir.path().join("level.dat");
+        fs::write(&level_file, &bytes).unwrap();
+
+        let manifest = parse_level(level_file, dir.path().join("level"), Provenance::default()).unwrap();
+
+        for moby in manifest.moby_models {
+            let start = moby.offset as usize;
+
+            prop_assert_eq!(
+                fs::read(moby.file).unwrap(),
+                &bytes[start..start + moby.length as usize]
+            );
+        }
+    }
+
+    #[test]
+    fn sky_exports_unless_an_index_is_out_of_range(bytes in fixtures::level::level()) {
+        let dir = tempfile::tempdir().unwrap();
+        let level_file = dir.path().join("level.dat");
+        fs::write(&level_file, &bytes).unwrap();
+
+        let manifest = parse_level(level_file, dir.path().join("level"), Provenance::default()).unwrap();
+
+        // the fixture's polygons are noise, most point past their tables
+        let sky = parse_sky(manifest.sky.clone()).unwrap();
+        let in_range = sky.sectors.iter().all(|sector| {
+            sector.polys.iter().all(|poly| {
+                poly.vertices.iter().all(|&i| (i as usize) < sector.vertices.len())
+                    && poly.colors.iter().all(|&i| (i as usize) < sector.colors.len())
+            })
+        });
+
+        let sky_glb = dir.path().join("sky.glb");
+        let exported = export_sky(&manifest, sky_glb.clone());
+
+        prop_assert_eq!(exported.is_ok(), in_range);
+        prop_assert_eq!(sky_glb.is_file(), in_range);
+    }
+}
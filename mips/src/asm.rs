@@ -0,0 +1,383 @@
+use std::collections::HashMap;
+
+use crate::instruction::{Encoding, Format, Instruction, OPS, REGISTERS};
+
+fn parse_register(operand: &str) -> anyhow::Result<u32> {
+    let name = operand.trim().trim_start_matches('$');
+
+    if let Ok(number) = name.parse::<u32>()
+        && number < 32
+    {
+        return Ok(number);
+    }
+
+    REGISTERS
+        .iter()
+        .position(|r| *r == name || (name == "s8" && *r == "fp"))
+        .map(|r| r as u32)
+        .ok_or_else(|| anyhow::anyhow!("unknown register `{operand}`"))
+}
+
+/// GTE registers only go by number, `$13` or `13`
+fn parse_gte_register(operand: &str) -> anyhow::Result<u32> {
+    operand
+        .trim()
+        .trim_start_matches('$')
+        .parse::<u32>()
+        .ok()
+        .filter(|number| *number < 32)
+        .ok_or_else(|| anyhow::anyhow!("unknown GTE register `{operand}`"))
+}
+
+fn parse_number(operand: &str) -> anyhow::Result<i64> {
+    let operand = operand.trim();
+    let (negative, digits) = match operand.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, operand),
+    };
+
+    let hex = digits
+        .strip_prefix("0x")
+        .or_else(|| digits.strip_prefix("0X"));
+
+    let value = match hex {
+        Some(hex) => i64::from_str_radix(hex, 16),
+        None => digits.parse::<i64>(),
+    }
+    .map_err(|_| anyhow::anyhow!("invalid number `{operand}`"))?;
+
+    Ok(if negative { -value } else { value })
+}
+
+// signed and unsigned values are both accepted as long as the bit
+// pattern fits, `ori $t0, $t0, -1` and `addiu $t0, $t0, 0xffff` are fine
+fn parse_imm16(operand: &str) -> anyhow::Result<u32> {
+    let value = parse_number(operand)?;
+
+    if !(-0x8000..=0xffff).contains(&value) {
+        anyhow::bail!("immediate `{operand}` doesn't fit in 16 bits");
+    }
+
+    Ok(value as u32 & 0xffff)
+}
+
+/// `offset(base)`
+fn parse_memory(operand: &str) -> anyhow::Result<(u32, u32)> {
+    let (offset, base) = operand
+        .trim()
+        .trim_end_matches(')')
+        .split_once('(')
+        .ok_or_else(|| anyhow::anyhow!("expected offset(base), got `{operand}`"))?;
+
+    let offset = if offset.trim().is_empty() {
+        0
+    } else {
+        parse_imm16(offset)?
+    };
+
+    Ok((offset, parse_register(base)?))
+}
+
+fn parse_target(operand: &str, labels: &HashMap<String, u32>) -> anyhow::Result<u32> {
+    let operand = operand.trim();
+
+    match labels.get(operand) {
+        Some(address) => Ok(*address),
+        None => Ok(parse_number(operand)? as u32),
+    }
+}
+
+fn expect_operands(mnemonic: &str, operands: &[&str], count: usize) -> anyhow::Result<()> {
+    if operands.len() != count {
+        anyhow::bail!(
+            "`{mnemonic}` takes {count} operands, got {}",
+            operands.len()
+        );
+    }
+
+    Ok(())
+}
+
+fn branch_offset(pc: u32, target: u32) -> anyhow::Result<u32> {
+    let delta = target as i64 - (pc as i64 + 4);
+
+    if delta % 4 != 0 || !(-0x20000..0x20000).contains(&delta) {
+        anyhow::bail!("branch target 0x{target:08x} is out of range from 0x{pc:08x}");
+    }
+
+    Ok((delta >> 2) as u32 & 0xffff)
+}
+
+fn encode(
+    mnemonic: &str,
+    operands: &[&str],
+    pc: u32,
+    labels: &HashMap<String, u32>,
+) -> anyhow::Result<u32> {
+    let op = OPS
+        .iter()
+        .find(|op| op.mnemonic == mnemonic)
+        .ok_or_else(|| anyhow::anyhow!("unknown instruction `{mnemonic}`"))?;
+
+    let (mut rs, mut rt, mut rd, mut shamt, mut low) = (0, 0, 0, 0, 0);
+
+    match op.format {
+        Format::RdRsRt => {
+            expect_operands(mnemonic, operands, 3)?;
+            rd = parse_register(operands[0])?;
+            rs = parse_register(operands[1])?;
+            rt = parse_register(operands[2])?;
+        }
+        Format::RdRtShamt => {
+            expect_operands(mnemonic, operands, 3)?;
+            rd = parse_register(operands[0])?;
+            rt = parse_register(operands[1])?;
+            shamt = parse_number(operands[2])? as u32;
+
+            if shamt > 31 {
+                anyhow::bail!("shift amount `{}` is out of range", operands[2]);
+            }
+        }
+        Format::RdRtRs => {
+            expect_operands(mnemonic, operands, 3)?;
+            rd = parse_register(operands[0])?;
+            rt = parse_register(operands[1])?;
+            rs = parse_register(operands[2])?;
+        }
+        Format::Rs => {
+            expect_operands(mnemonic, operands, 1)?;
+            rs = parse_register(operands[0])?;
+        }
+        Format::RdRs => match operands.len() {
+            // jalr rs links into $ra
+            1 => {
+                rd = 31;
+                rs = parse_register(operands[0])?;
+            }
+            _ => {
+                expect_operands(mnemonic, operands, 2)?;
+                rd = parse_register(operands[0])?;
+                rs = parse_register(operands[1])?;
+            }
+        },
+        Format::Rd => {
+            expect_operands(mnemonic, operands, 1)?;
+            rd = parse_register(operands[0])?;
+        }
+        Format::RsRt => {
+            expect_operands(mnemonic, operands, 2)?;
+            rs = parse_register(operands[0])?;
+            rt = parse_register(operands[1])?;
+        }
+        Format::None => {
+            expect_operands(mnemonic, operands, 0)?;
+        }
+        Format::RtRsImm => {
+            expect_operands(mnemonic, operands, 3)?;
+            rt = parse_register(operands[0])?;
+            rs = parse_register(operands[1])?;
+            low = parse_imm16(operands[2])?;
+        }
+        Format::RtImm => {
+            expect_operands(mnemonic, operands, 2)?;
+            rt = parse_register(operands[0])?;
+            low = parse_imm16(operands[1])?;
+        }
+        Format::RsRtBranch => {
+            expect_operands(mnemonic, operands, 3)?;
+            rs = parse_register(operands[0])?;
+            rt = parse_register(operands[1])?;
+            low = branch_offset(pc, parse_target(operands[2], labels)?)?;
+        }
+        Format::RsBranch => {
+            expect_operands(mnemonic, operands, 2)?;
+            rs = parse_register(operands[0])?;
+            low = branch_offset(pc, parse_target(operands[1], labels)?)?;
+        }
+        Format::Jump => {
+            expect_operands(mnemonic, operands, 1)?;
+            let target = parse_target(operands[0], labels)?;
+
+            if target % 4 != 0 || (target & 0xf000_0000) != (pc.wrapping_add(4) & 0xf000_0000) {
+                anyhow::bail!("jump target 0x{target:08x} is unreachable from 0x{pc:08x}");
+            }
+
+            low = (target >> 2) & 0x03ff_ffff;
+        }
+        Format::RtMem => {
+            expect_operands(mnemonic, operands, 2)?;
+            rt = parse_register(operands[0])?;
+            (low, rs) = parse_memory(operands[1])?;
+        }
+        Format::GteMem => {
+            expect_operands(mnemonic, operands, 2)?;
+            rt = parse_gte_register(operands[0])?;
+            (low, rs) = parse_memory(operands[1])?;
+        }
+        Format::RtRd => {
+            expect_operands(mnemonic, operands, 2)?;
+            rt = parse_register(operands[0])?;
+            rd = parse_register(operands[1])?;
+        }
+        Format::Cop => {
+            expect_operands(mnemonic, operands, 1)?;
+            low = parse_number(operands[0])? as u32 & 0x01ff_ffff;
+        }
+    }
+
+    let word = match op.encoding {
+        Encoding::Opcode(opcode) => (opcode << 26) | (rs << 21) | (rt << 16) | low,
+        Encoding::Special(funct) => (rs << 21) | (rt << 16) | (rd << 11) | (shamt << 6) | funct,
+        Encoding::RegImm(rt) => (1 << 26) | (rs << 21) | (rt << 16) | low,
+        Encoding::Cop(opcode, rs) => (opcode << 26) | (rs << 21) | (rt << 16) | (rd << 11),
+        Encoding::CopFunct(opcode, bits) => (opcode << 26) | bits | low,
+    };
+
+    Ok(word)
+}
+
+/// Rewrites pseudo instructions into real ones
+fn expand<'a>(
+    mnemonic: &'a str,
+    operands: &[&'a str],
+) -> anyhow::Result<Vec<(String, Vec<String>)>> {
+    let real = |mnemonic: &str, operands: &[&str]| {
+        (
+            mnemonic.to_string(),
+            operands.iter().map(|o| o.to_string()).collect::<Vec<_>>(),
+        )
+    };
+
+    Ok(match mnemonic {
+        "nop" => vec![real("sll", &["$zero", "$zero", "0"])],
+        "move" => {
+            expect_operands(mnemonic, operands, 2)?;
+            vec![real("addu", &[operands[0], operands[1], "$zero"])]
+        }
+        "b" => {
+            expect_operands(mnemonic, operands, 1)?;
+            vec![real("beq", &["$zero", "$zero", operands[0]])]
+        }
+        "beqz" | "bnez" => {
+            expect_operands(mnemonic, operands, 2)?;
+            let real_mnemonic = if mnemonic == "beqz" { "beq" } else { "bne" };
+            vec![real(real_mnemonic, &[operands[0], "$zero", operands[1]])]
+        }
+        "li" => {
+            expect_operands(mnemonic, operands, 2)?;
+            let value = parse_number(operands[1])?;
+
+            if (i16::MIN as i64..=i16::MAX as i64).contains(&value) {
+                vec![real("addiu", &[operands[0], "$zero", operands[1]])]
+            } else if (0..=u16::MAX as i64).contains(&value) {
+                vec![real("ori", &[operands[0], "$zero", operands[1]])]
+            } else {
+                let value = value as u32;
+                let hi = format!("0x{:x}", value >> 16);
+                let lo = format!("0x{:x}", value & 0xffff);
+
+                vec![
+                    real("lui", &[operands[0], &hi]),
+                    real("ori", &[operands[0], operands[0], &lo]),
+                ]
+            }
+        }
+        _ => vec![real(mnemonic, operands)],
+    })
+}
+
+enum Statement {
+    Word(String),
+    Instruction(String, Vec<String>),
+}
+
+/// Assembles `lines` to be placed at `address`. Supports `label:`,
+/// `.word`, `#`/`;` comments and the nop, move, b, beqz, bnez and li
+/// pseudo instructions. Branch and jump targets can be labels or addresses.
+pub fn assemble(lines: &[String], address: u32) -> anyhow::Result<Vec<u32>> {
+    let mut labels = HashMap::new();
+    let mut statements = Vec::new();
+
+    // first pass, expand and place labels
+    for (number, line) in lines.iter().enumerate() {
+        let line = line.split(['#', ';']).next().unwrap_or_default().trim();
+
+        let line = match line.split_once(':') {
+            Some((label, rest)) => {
+                let pc = address + statements.len() as u32 * 4;
+
+                if labels.insert(label.trim().to_string(), pc).is_some() {
+                    anyhow::bail!("line {}: duplicate label `{}`", number + 1, label.trim());
+                }
+
+                rest.trim()
+            }
+            None => line,
+        };
+
+        if line.is_empty() {
+            continue;
+        }
+
+        let (mnemonic, operands) = match line.split_once(char::is_whitespace) {
+            Some((mnemonic, operands)) => (mnemonic, operands.split(',').map(str::trim).collect()),
+            None => (line, Vec::new()),
+        };
+
+        if mnemonic == ".word" {
+            for operand in operands {
+                statements.push((number, Statement::Word(operand.to_string())));
+            }
+
+            continue;
+        }
+
+        let expanded =
+            expand(mnemonic, &operands).map_err(|e| anyhow::anyhow!("line {}: {e}", number + 1))?;
+
+        for (mnemonic, operands) in expanded {
+            statements.push((number, Statement::Instruction(mnemonic, operands)));
+        }
+    }
+
+    // second pass, encode with every label known
+    statements
+        .iter()
+        .enumerate()
+        .map(|(i, (number, statement))| {
+            let pc = address + i as u32 * 4;
+
+            match statement {
+                Statement::Word(value) => parse_target(value, &labels),
+                Statement::Instruction(mnemonic, operands) => {
+                    let operands = operands.iter().map(String::as_str).collect::<Vec<_>>();
+
+                    encode(mnemonic, &operands, pc, &labels)
+                }
+            }
+            .map_err(|e| anyhow::anyhow!("line {}: {e}", number + 1))
+        })
+        .collect()
+}
+
+/// Makes sure no branch or jump is left without its delay slot,
+/// and that delay slots don't hold another branch or jump
+pub fn check_delay_slots(words: &[u32], address: u32) -> anyhow::Result<()> {
+    for (i, word) in words.iter().enumerate() {
+        if !Instruction(*word).has_delay_slot() {
+            continue;
+        }
+
+        let pc = address + i as u32 * 4;
+
+        match words.get(i + 1) {
+            None => anyhow::bail!("branch at 0x{pc:08x} has no delay slot"),
+            Some(slot) if Instruction(*slot).has_delay_slot() => {
+                anyhow::bail!("branch at 0x{pc:08x} has a branch in its delay slot")
+            }
+            Some(_) => {}
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,290 @@
+use std::{collections::HashMap, fmt::Write as _};
+
+pub const REGISTERS: [&str; 32] = [
+    "zero", "at", "v0", "v1", "a0", "a1", "a2", "a3", "t0", "t1", "t2", "t3", "t4", "t5", "t6",
+    "t7", "s0", "s1", "s2", "s3", "s4", "s5", "s6", "s7", "t8", "t9", "k0", "k1", "gp", "sp", "fp",
+    "ra",
+];
+
+/// Operand layout of an instruction, in assembly order
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// add rd, rs, rt
+    RdRsRt,
+    /// sll rd, rt, shamt
+    RdRtShamt,
+    /// sllv rd, rt, rs
+    RdRtRs,
+    /// jr rs
+    Rs,
+    /// jalr rd, rs
+    RdRs,
+    /// mfhi rd
+    Rd,
+    /// mult rs, rt
+    RsRt,
+    /// syscall
+    None,
+    /// addiu rt, rs, imm
+    RtRsImm,
+    /// lui rt, imm
+    RtImm,
+    /// beq rs, rt, target
+    RsRtBranch,
+    /// blez rs, target
+    RsBranch,
+    /// j target
+    Jump,
+    /// lw rt, offset(rs)
+    RtMem,
+    /// lwc2 gte_rt, offset(rs), rt is a GTE data register
+    GteMem,
+    /// mfc0 rt, rd
+    RtRd,
+    /// cop2 imm25
+    Cop,
+}
+
+/// Where the fixed bits of an instruction live
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Encoding {
+    /// primary opcode only
+    Opcode(u32),
+    /// opcode 0, function field
+    Special(u32),
+    /// opcode 1, rt field
+    RegImm(u32),
+    /// coprocessor opcode, rs field
+    Cop(u32, u32),
+    /// coprocessor opcode, fixed low bits
+    CopFunct(u32, u32),
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Op {
+    pub mnemonic: &'static str,
+    pub format: Format,
+    pub encoding: Encoding,
+}
+
+const fn op(mnemonic: &'static str, format: Format, encoding: Encoding) -> Op {
+    Op {
+        mnemonic,
+        format,
+        encoding,
+    }
+}
+
+/// MIPS R3000A instruction set, plus the GTE (cop2) transfers
+pub const OPS: &[Op] = &[
+    op("sll", Format::RdRtShamt, Encoding::Special(0x00)),
+    op("srl", Format::RdRtShamt, Encoding::Special(0x02)),
+    op("sra", Format::RdRtShamt, Encoding::Special(0x03)),
+    op("sllv", Format::RdRtRs, Encoding::Special(0x04)),
+    op("srlv", Format::RdRtRs, Encoding::Special(0x06)),
+    op("srav", Format::RdRtRs, Encoding::Special(0x07)),
+    op("jr", Format::Rs, Encoding::Special(0x08)),
+    op("jalr", Format::RdRs, Encoding::Special(0x09)),
+    op("syscall", Format::None, Encoding::Special(0x0c)),
+    op("break", Format::None, Encoding::Special(0x0d)),
+    op("mfhi", Format::Rd, Encoding::Special(0x10)),
+    op("mthi", Format::Rs, Encoding::Special(0x11)),
+    op("mflo", Format::Rd, Encoding::Special(0x12)),
+    op("mtlo", Format::Rs, Encoding::Special(0x13)),
+    op("mult", Format::RsRt, Encoding::Special(0x18)),
+    op("multu", Format::RsRt, Encoding::Special(0x19)),
+    op("div", Format::RsRt, Encoding::Special(0x1a)),
+    op("divu", Format::RsRt, Encoding::Special(0x1b)),
+    op("add", Format::RdRsRt, Encoding::Special(0x20)),
+    op("addu", Format::RdRsRt, Encoding::Special(0x21)),
+    op("sub", Format::RdRsRt, Encoding::Special(0x22)),
+    op("subu", Format::RdRsRt, Encoding::Special(0x23)),
+    op("and", Format::RdRsRt, Encoding::Special(0x24)),
+    op("or", Format::RdRsRt, Encoding::Special(0x25)),
+    op("xor", Format::RdRsRt, Encoding::Special(0x26)),
+    op("nor", Format::RdRsRt, Encoding::Special(0x27)),
+    op("slt", Format::RdRsRt, Encoding::Special(0x2a)),
+    op("sltu", Format::RdRsRt, Encoding::Special(0x2b)),
+    op("bltz", Format::RsBranch, Encoding::RegImm(0x00)),
+    op("bgez", Format::RsBranch, Encoding::RegImm(0x01)),
+    op("bltzal", Format::RsBranch, Encoding::RegImm(0x10)),
+    op("bgezal", Format::RsBranch, Encoding::RegImm(0x11)),
+    op("j", Format::Jump, Encoding::Opcode(0x02)),
+    op("jal", Format::Jump, Encoding::Opcode(0x03)),
+    op("beq", Format::RsRtBranch, Encoding::Opcode(0x04)),
+    op("bne", Format::RsRtBranch, Encoding::Opcode(0x05)),
+    op("blez", Format::RsBranch, Encoding::Opcode(0x06)),
+    op("bgtz", Format::RsBranch, Encoding::Opcode(0x07)),
+    op("addi", Format::RtRsImm, Encoding::Opcode(0x08)),
+    op("addiu", Format::RtRsImm, Encoding::Opcode(0x09)),
+    op("slti", Format::RtRsImm, Encoding::Opcode(0x0a)),
+    op("sltiu", Format::RtRsImm, Encoding::Opcode(0x0b)),
+    op("andi", Format::RtRsImm, Encoding::Opcode(0x0c)),
+    op("ori", Format::RtRsImm, Encoding::Opcode(0x0d)),
+    op("xori", Format::RtRsImm, Encoding::Opcode(0x0e)),
+    op("lui", Format::RtImm, Encoding::Opcode(0x0f)),
+    op("mfc0", Format::RtRd, Encoding::Cop(0x10, 0x00)),
+    op("mtc0", Format::RtRd, Encoding::Cop(0x10, 0x04)),
+    op("rfe", Format::None, Encoding::CopFunct(0x10, 0x0200_0010)),
+    op("mfc2", Format::RtRd, Encoding::Cop(0x12, 0x00)),
+    op("cfc2", Format::RtRd, Encoding::Cop(0x12, 0x02)),
+    op("mtc2", Format::RtRd, Encoding::Cop(0x12, 0x04)),
+    op("ctc2", Format::RtRd, Encoding::Cop(0x12, 0x06)),
+    op("cop2", Format::Cop, Encoding::CopFunct(0x12, 0x0200_0000)),
+    op("lb", Format::RtMem, Encoding::Opcode(0x20)),
+    op("lh", Format::RtMem, Encoding::Opcode(0x21)),
+    op("lwl", Format::RtMem, Encoding::Opcode(0x22)),
+    op("lw", Format::RtMem, Encoding::Opcode(0x23)),
+    op("lbu", Format::RtMem, Encoding::Opcode(0x24)),
+    op("lhu", Format::RtMem, Encoding::Opcode(0x25)),
+    op("lwr", Format::RtMem, Encoding::Opcode(0x26)),
+    op("sb", Format::RtMem, Encoding::Opcode(0x28)),
+    op("sh", Format::RtMem, Encoding::Opcode(0x29)),
+    op("swl", Format::RtMem, Encoding::Opcode(0x2a)),
+    op("sw", Format::RtMem, Encoding::Opcode(0x2b)),
+    op("swr", Format::RtMem, Encoding::Opcode(0x2e)),
+    op("lwc2", Format::GteMem, Encoding::Opcode(0x32)),
+    op("swc2", Format::GteMem, Encoding::Opcode(0x3a)),
+];
+
+/// Raw instruction word with its field accessors
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Instruction(pub u32);
+
+impl Instruction {
+    pub fn opcode(&self) -> u32 {
+        self.0 >> 26
+    }
+
+    pub fn rs(&self) -> u32 {
+        (self.0 >> 21) & 0x1f
+    }
+
+    pub fn rt(&self) -> u32 {
+        (self.0 >> 16) & 0x1f
+    }
+
+    pub fn rd(&self) -> u32 {
+        (self.0 >> 11) & 0x1f
+    }
+
+    pub fn shamt(&self) -> u32 {
+        (self.0 >> 6) & 0x1f
+    }
+
+    pub fn funct(&self) -> u32 {
+        self.0 & 0x3f
+    }
+
+    pub fn imm(&self) -> u16 {
+        self.0 as u16
+    }
+
+    pub fn simm(&self) -> i16 {
+        self.0 as i16
+    }
+
+    /// Finds the table entry describing this word
+    pub fn op(&self) -> Option<&'static Op> {
+        OPS.iter().find(|op| match op.encoding {
+            Encoding::Opcode(opcode) => self.opcode() == opcode,
+            Encoding::Special(funct) => self.opcode() == 0 && self.funct() == funct,
+            Encoding::RegImm(rt) => self.opcode() == 1 && self.rt() == rt,
+            Encoding::Cop(opcode, rs) => {
+                self.opcode() == opcode && self.rs() == rs && self.0 & 0x7ff == 0
+            }
+            Encoding::CopFunct(opcode, bits) => {
+                self.opcode() == opcode
+                    && match op.format {
+                        Format::Cop => self.0 & 0x0200_0000 != 0,
+                        _ => self.0 & 0x03ff_ffff == bits,
+                    }
+            }
+        })
+    }
+
+    /// Address a branch or jump at `pc` goes to
+    pub fn target(&self, pc: u32) -> Option<u32> {
+        match self.op()?.format {
+            Format::RsRtBranch | Format::RsBranch => Some(
+                pc.wrapping_add(4)
+                    .wrapping_add(((self.simm() as i32) << 2) as u32),
+            ),
+            Format::Jump => {
+                Some((pc.wrapping_add(4) & 0xf000_0000) | ((self.0 & 0x03ff_ffff) << 2))
+            }
+            _ => None,
+        }
+    }
+
+    /// Branches and jumps execute the following instruction before they land
+    pub fn has_delay_slot(&self) -> bool {
+        self.op().is_some_and(|op| {
+            matches!(
+                op.format,
+                Format::RsRtBranch | Format::RsBranch | Format::Jump
+            ) || matches!(
+                op.encoding,
+                Encoding::Special(0x08) | Encoding::Special(0x09)
+            )
+        })
+    }
+
+    /// Disassembles the word at `pc`, naming targets found in `symbols`
+    pub fn disassemble(&self, pc: u32, symbols: &HashMap<u32, String>) -> String {
+        if self.0 == 0 {
+            return "nop".to_string();
+        }
+
+        let Some(op) = self.op() else {
+            return format!(".word 0x{:08x}", self.0);
+        };
+
+        let reg = |r: u32| format!("${}", REGISTERS[r as usize]);
+        let target = || {
+            let address = self.target(pc).unwrap_or(0);
+
+            symbols
+                .get(&address)
+                .cloned()
+                .unwrap_or_else(|| format!("0x{address:08x}"))
+        };
+
+        let mut text = op.mnemonic.to_string();
+
+        let operands = match op.format {
+            Format::RdRsRt => format!("{}, {}, {}", reg(self.rd()), reg(self.rs()), reg(self.rt())),
+            Format::RdRtShamt => {
+                format!("{}, {}, {}", reg(self.rd()), reg(self.rt()), self.shamt())
+            }
+            Format::RdRtRs => format!("{}, {}, {}", reg(self.rd()), reg(self.rt()), reg(self.rs())),
+            Format::Rs => reg(self.rs()),
+            Format::RdRs => format!("{}, {}", reg(self.rd()), reg(self.rs())),
+            Format::Rd => reg(self.rd()),
+            Format::RsRt => format!("{}, {}", reg(self.rs()), reg(self.rt())),
+            Format::None => String::new(),
+            Format::RtRsImm => match op.mnemonic {
+                "andi" | "ori" | "xori" => {
+                    format!("{}, {}, 0x{:x}", reg(self.rt()), reg(self.rs()), self.imm())
+                }
+                _ => format!("{}, {}, {}", reg(self.rt()), reg(self.rs()), self.simm()),
+            },
+            Format::RtImm => format!("{}, 0x{:x}", reg(self.rt()), self.imm()),
+            Format::RsRtBranch => {
+                format!("{}, {}, {}", reg(self.rs()), reg(self.rt()), target())
+            }
+            Format::RsBranch => format!("{}, {}", reg(self.rs()), target()),
+            Format::Jump => target(),
+            Format::RtMem => format!("{}, {}({})", reg(self.rt()), self.simm(), reg(self.rs())),
+            Format::GteMem => format!("${}, {}({})", self.rt(), self.simm(), reg(self.rs())),
+            Format::RtRd => format!("{}, ${}", reg(self.rt()), self.rd()),
+            Format::Cop => format!("0x{:x}", self.0 & 0x01ff_ffff),
+        };
+
+        if !operands.is_empty() {
+            let _ = write!(text, " {operands}");
+        }
+
+        text
+    }
+}
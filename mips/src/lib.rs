@@ -0,0 +1,4 @@
+pub mod asm;
+pub mod instruction;
+pub mod overlay;
+pub mod patch;
@@ -0,0 +1,142 @@
+use std::{collections::HashMap, fs, path::PathBuf};
+
+use crate::instruction::{Format, Instruction};
+
+/// RAM address the level overlays are loaded at
+pub const LEVEL_OVERLAY_BASE: u32 = 0x8007_3800;
+
+pub struct Overlay {
+    pub base: u32,
+    pub data: Vec<u8>,
+}
+
+pub struct Line {
+    pub address: u32,
+    pub instruction: Instruction,
+    pub label: Option<String>,
+    pub text: String,
+}
+
+impl Overlay {
+    pub fn load(overlay_file: PathBuf, base: u32) -> anyhow::Result<Self> {
+        let data = fs::read(&overlay_file)?;
+
+        Ok(Self { base, data })
+    }
+
+    pub fn end(&self) -> u32 {
+        self.base + self.data.len() as u32
+    }
+
+    pub fn contains(&self, address: u32) -> bool {
+        address >= self.base && address < self.end()
+    }
+
+    pub fn word(&self, address: u32) -> Option<u32> {
+        let offset = address.checked_sub(self.base)? as usize;
+        let bytes = self.data.get(offset..offset + 4)?;
+
+        Some(u32::from_le_bytes(bytes.try_into().ok()?))
+    }
+
+    pub fn words(&self) -> impl Iterator<Item = (u32, u32)> + '_ {
+        self.data.chunks_exact(4).enumerate().map(|(i, bytes)| {
+            (
+                self.base + i as u32 * 4,
+                u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+            )
+        })
+    }
+
+    /// The overlay starts with a table of pointers to its own functions,
+    /// it ends at the first word that doesn't point back into the overlay
+    pub fn entries(&self) -> Vec<u32> {
+        self.words()
+            .map(|(_, word)| word)
+            .take_while(|word| self.contains(*word) && word % 4 == 0)
+            .collect()
+    }
+
+    /// Names the entry points, call targets and branch targets
+    pub fn symbols(&self) -> HashMap<u32, String> {
+        let mut symbols = HashMap::new();
+
+        for (i, entry) in self.entries().iter().enumerate() {
+            symbols.insert(*entry, format!("entry_{i}"));
+        }
+
+        let code_start = self.base + self.entries().len() as u32 * 4;
+
+        for (address, word) in self.words().filter(|(address, _)| *address >= code_start) {
+            let instruction = Instruction(word);
+
+            let Some(target) = instruction.target(address) else {
+                continue;
+            };
+
+            if !self.contains(target) {
+                continue;
+            }
+
+            let name = match instruction.op().map(|op| (op.mnemonic, op.format)) {
+                Some(("jal", _)) => format!("fn_{target:08x}"),
+                Some((_, Format::Jump)) => format!("loc_{target:08x}"),
+                _ => format!(".L{target:08x}"),
+            };
+
+            // entries and functions win over plain labels
+            symbols.entry(target).or_insert(name);
+        }
+
+        symbols
+    }
+
+    pub fn disassemble(&self) -> Vec<Line> {
+        let symbols = self.symbols();
+        let code_start = self.base + self.entries().len() as u32 * 4;
+
+        self.words()
+            .map(|(address, word)| {
+                let instruction = Instruction(word);
+
+                // the entry table is data, not code
+                let text = if address < code_start {
+                    format!(
+                        ".word {}",
+                        symbols
+                            .get(&word)
+                            .cloned()
+                            .unwrap_or_else(|| format!("0x{word:08x}"))
+                    )
+                } else {
+                    instruction.disassemble(address, &symbols)
+                };
+
+                Line {
+                    address,
+                    instruction,
+                    label: symbols.get(&address).cloned(),
+                    text,
+                }
+            })
+            .collect()
+    }
+
+    /// Full listing with labels, addresses and raw words
+    pub fn listing(&self) -> String {
+        let mut listing = String::new();
+
+        for line in self.disassemble() {
+            if let Some(label) = line.label {
+                listing.push_str(&format!("\n{label}:\n"));
+            }
+
+            listing.push_str(&format!(
+                "    /* {:08x} {:08x} */ {}\n",
+                line.address, line.instruction.0, line.text
+            ));
+        }
+
+        listing
+    }
+}
@@ -0,0 +1,102 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{Seek, Write},
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::asm::{assemble, check_delay_slots};
+
+/// Code patch against an overlay (by its level file name) or SCUS_944.25
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Patch {
+    pub target: String,
+    /// RAM address of the first instruction, in hex
+    pub address: String,
+    pub code: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PatchFile {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub patches: Vec<Patch>,
+}
+
+/// How RAM addresses of a target map onto its file
+#[derive(Copy, Clone, Debug)]
+pub struct Region {
+    /// RAM address of the first byte after `header_len`
+    pub base: u32,
+    pub header_len: u64,
+}
+
+impl Region {
+    pub fn file_offset(&self, address: u32) -> Option<u64> {
+        Some(address.checked_sub(self.base)? as u64 + self.header_len)
+    }
+}
+
+impl Patch {
+    pub fn address(&self) -> anyhow::Result<u32> {
+        Ok(u32::from_str_radix(
+            self.address.trim().trim_start_matches("0x"),
+            16,
+        )?)
+    }
+
+    /// Assembles the patch and checks its delay slots
+    pub fn assemble(&self) -> anyhow::Result<Vec<u32>> {
+        let address = self.address()?;
+
+        if address % 4 != 0 {
+            anyhow::bail!("patch address 0x{address:08x} isn't word aligned");
+        }
+
+        let words = assemble(&self.code, address)?;
+        check_delay_slots(&words, address)?;
+
+        Ok(words)
+    }
+
+    /// Writes the assembled code into `target_file`, returns the word count
+    pub fn apply(&self, target_file: PathBuf, region: Region) -> anyhow::Result<usize> {
+        let address = self.address()?;
+        let words = self.assemble()?;
+
+        let mut file = OpenOptions::new().write(true).open(&target_file)?;
+        let file_len = file.metadata()?.len();
+
+        let offset = region.file_offset(address).ok_or_else(|| {
+            anyhow::anyhow!(
+                "0x{address:08x} is below the start of {} (0x{:08x})",
+                self.target,
+                region.base
+            )
+        })?;
+
+        if offset + words.len() as u64 * 4 > file_len {
+            anyhow::bail!(
+                "patch at 0x{address:08x} ({} bytes) runs past the end of {}",
+                words.len() * 4,
+                self.target
+            );
+        }
+
+        file.seek(std::io::SeekFrom::Start(offset))?;
+
+        for word in words.iter() {
+            file.write_all(&word.to_le_bytes())?;
+        }
+
+        Ok(words.len())
+    }
+}
+
+pub fn read_patch_file(patch_file: &Path) -> anyhow::Result<PatchFile> {
+    let patch_file = File::open(patch_file)?;
+
+    Ok(serde_json::from_reader(patch_file)?)
+}
@@ -0,0 +1,65 @@
+use mips::asm::assemble;
+
+const BASE: u32 = 0x8001_0000;
+
+fn asm(lines: &[&str]) -> Vec<u32> {
+    let lines = lines
+        .iter()
+        .map(|line| line.to_string())
+        .collect::<Vec<_>>();
+
+    assemble(&lines, BASE).unwrap()
+}
+
+#[test]
+fn encodes_r_type() {
+    assert_eq!(asm(&["addu $v0, $a0, $a1"]), [0x0085_1021]);
+    assert_eq!(asm(&["sll $t0, $t1, 4"]), [0x0009_4100]);
+    assert_eq!(asm(&["jr $ra"]), [0x03e0_0008]);
+    assert_eq!(asm(&["jalr $t9"]), [0x0320_f809]);
+}
+
+#[test]
+fn encodes_i_type() {
+    assert_eq!(asm(&["addiu $sp, $sp, -24"]), [0x27bd_ffe8]);
+    assert_eq!(asm(&["lw $ra, 20($sp)"]), [0x8fbf_0014]);
+    assert_eq!(asm(&["sw $ra, 20($sp)"]), [0xafbf_0014]);
+    assert_eq!(asm(&["andi $v0, $v0, 0xff"]), [0x3042_00ff]);
+}
+
+#[test]
+fn encodes_j_type() {
+    assert_eq!(asm(&["j 0x80010000"]), [0x0800_4000]);
+    assert_eq!(asm(&["jal 0x80012340"]), [0x0c00_48d0]);
+}
+
+#[test]
+fn encodes_branch_offsets_from_the_delay_slot() {
+    assert_eq!(asm(&["beq $zero, $zero, 0x80010010"]), [0x1000_0003]);
+    assert_eq!(
+        asm(&["loop:", "nop", "nop", "bne $t0, $zero, loop"]),
+        [0, 0, 0x1500_fffd]
+    );
+    assert_eq!(asm(&["bgez $a0, 0x80010004"]), [0x0481_0000]);
+}
+
+#[test]
+fn rejects_branches_out_of_range() {
+    let lines = ["beq $zero, $zero, 0x80040000".to_string()];
+
+    assert!(assemble(&lines, BASE).is_err());
+}
+
+#[test]
+fn expands_li() {
+    assert_eq!(asm(&["li $t0, 5"]), [0x2408_0005]);
+    assert_eq!(asm(&["li $t0, -1"]), [0x2408_ffff]);
+    assert_eq!(asm(&["li $t0, 0xffff"]), [0x3408_ffff]);
+    assert_eq!(asm(&["li $t0, 0x80012345"]), [0x3c08_8001, 0x3508_2345]);
+}
+
+#[test]
+fn accepts_an_uppercase_hex_prefix() {
+    assert_eq!(asm(&["li $t0, 0X10"]), [0x2408_0010]);
+    assert_eq!(asm(&[".word 0XDEADBEEF"]), [0xdead_beef]);
+}
@@ -0,0 +1,70 @@
+use std::collections::HashMap;
+
+use mips::{
+    asm::{assemble, check_delay_slots},
+    instruction::Instruction,
+};
+use proptest::prelude::*;
+
+const BASE: u32 = 0x8001_0000;
+
+fn disasm(word: u32) -> String {
+    Instruction(word).disassemble(BASE, &HashMap::new())
+}
+
+#[test]
+fn names_gte_registers_by_number() {
+    assert_eq!(disasm(0xc880_0000), "lwc2 $0, 0($a0)");
+    assert_eq!(disasm(0xe8ae_0004), "swc2 $14, 4($a1)");
+    assert_eq!(
+        assemble(&["swc2 $14, 4($a1)".to_string()], BASE).unwrap(),
+        [0xe8ae_0004]
+    );
+    assert!(assemble(&["lwc2 $t0, 0($a0)".to_string()], BASE).is_err());
+}
+
+#[test]
+fn names_symbols_at_branch_targets() {
+    let symbols = HashMap::from([(0x8001_0010, "func_80010010".to_string())]);
+
+    assert_eq!(
+        Instruction(0x0c00_4004).disassemble(BASE, &symbols),
+        "jal func_80010010"
+    );
+}
+
+#[test]
+fn delay_slots_must_exist() {
+    let words = assemble(&["jr $ra".to_string()], BASE).unwrap();
+
+    assert!(check_delay_slots(&words, BASE).is_err());
+}
+
+#[test]
+fn delay_slots_cant_hold_branches_or_jumps() {
+    let lines = ["beq $zero, $zero, 0x80010010", "jr $ra", "nop"].map(String::from);
+    let words = assemble(&lines, BASE).unwrap();
+
+    assert!(check_delay_slots(&words, BASE).is_err());
+}
+
+#[test]
+fn delay_slots_can_hold_anything_else() {
+    let lines = ["jr $ra", "addiu $sp, $sp, 24"].map(String::from);
+    let words = assemble(&lines, BASE).unwrap();
+
+    assert!(check_delay_slots(&words, BASE).is_ok());
+}
+
+proptest! {
+    #[test]
+    fn disassembly_assembles_back(word in any::<u32>()) {
+        let text = disasm(word);
+
+        let words = assemble(std::slice::from_ref(&text), BASE).unwrap();
+
+        prop_assert_eq!(words.len(), 1);
+        // fields the instruction ignores don't survive, the text does
+        prop_assert_eq!(disasm(words[0]), text);
+    }
+}
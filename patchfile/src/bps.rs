@@ -0,0 +1,312 @@
+use std::collections::HashMap;
+
+const MAGIC: &[u8; 4] = b"BPS1";
+
+const SOURCE_READ: u64 = 0;
+const TARGET_READ: u64 = 1;
+const SOURCE_COPY: u64 = 2;
+const TARGET_COPY: u64 = 3;
+
+/// Source blocks are indexed at this alignment for SourceCopy matches
+const BLOCK_LEN: usize = 256;
+/// Shortest same-offset run worth a SourceRead
+const MIN_SOURCE_READ: usize = 32;
+
+const HASH_BASE: u64 = 0x100_0000_01b3;
+
+fn write_number(patch: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let x = (value & 0x7f) as u8;
+        value >>= 7;
+
+        if value == 0 {
+            patch.push(0x80 | x);
+            break;
+        }
+
+        patch.push(x);
+        value -= 1;
+    }
+}
+
+fn read_number(patch: &[u8], cursor: &mut usize) -> anyhow::Result<u64> {
+    let mut value = 0u64;
+    let mut shift = 1u64;
+
+    loop {
+        let x = *patch
+            .get(*cursor)
+            .ok_or_else(|| anyhow::anyhow!("truncated BPS number at 0x{:x}", *cursor))?;
+        *cursor += 1;
+
+        value = value
+            .checked_add((x & 0x7f) as u64 * shift)
+            .ok_or_else(|| anyhow::anyhow!("BPS number overflows at 0x{:x}", *cursor))?;
+
+        if x & 0x80 != 0 {
+            return Ok(value);
+        }
+
+        shift <<= 7;
+        value += shift;
+    }
+}
+
+fn block_hash(block: &[u8]) -> u64 {
+    block.iter().fold(0u64, |h, &b| {
+        h.wrapping_mul(HASH_BASE).wrapping_add(b as u64)
+    })
+}
+
+struct Encoder {
+    patch: Vec<u8>,
+    /// Start of the pending TargetRead bytes
+    literal_start: usize,
+    source_relative: usize,
+}
+
+impl Encoder {
+    fn action(&mut self, command: u64, len: usize) {
+        write_number(&mut self.patch, ((len as u64 - 1) << 2) | command);
+    }
+
+    fn flush_literal(&mut self, target: &[u8], end: usize) {
+        if end > self.literal_start {
+            self.action(TARGET_READ, end - self.literal_start);
+            self.patch
+                .extend_from_slice(&target[self.literal_start..end]);
+        }
+    }
+}
+
+/// Builds a BPS patch turning `source` into `target`. Unchanged data at the
+/// same offset becomes SourceRead, data moved around (a WAD entry growing
+/// pushes everything after it) is found through an index of source blocks.
+pub fn make_bps(source: &[u8], target: &[u8]) -> Vec<u8> {
+    let mut index = HashMap::new();
+    for offset in (0..source.len().saturating_sub(BLOCK_LEN - 1)).step_by(BLOCK_LEN) {
+        index
+            .entry(block_hash(&source[offset..offset + BLOCK_LEN]))
+            .or_insert(offset);
+    }
+
+    // weight of the byte leaving the rolling window
+    let out_weight = (1..BLOCK_LEN).fold(1u64, |w, _| w.wrapping_mul(HASH_BASE));
+
+    let mut encoder = Encoder {
+        patch: MAGIC.to_vec(),
+        literal_start: 0,
+        source_relative: 0,
+    };
+
+    write_number(&mut encoder.patch, source.len() as u64);
+    write_number(&mut encoder.patch, target.len() as u64);
+    // no metadata
+    write_number(&mut encoder.patch, 0);
+
+    let mut i = 0;
+    let mut hash = None;
+
+    while i < target.len() {
+        let same_offset = (i..target.len())
+            .take_while(|&j| source.get(j) == Some(&target[j]))
+            .take(MIN_SOURCE_READ)
+            .count();
+
+        if same_offset == MIN_SOURCE_READ || (same_offset > 0 && i + same_offset == target.len()) {
+            let len = (i..target.len())
+                .take_while(|&j| source.get(j) == Some(&target[j]))
+                .count();
+
+            encoder.flush_literal(target, i);
+            encoder.action(SOURCE_READ, len);
+
+            i += len;
+            encoder.literal_start = i;
+            hash = None;
+            continue;
+        }
+
+        if i + BLOCK_LEN <= target.len() {
+            let h = match hash {
+                Some(h) => h,
+                None => block_hash(&target[i..i + BLOCK_LEN]),
+            };
+
+            if let Some(&offset) = index.get(&h)
+                && source[offset..offset + BLOCK_LEN] == target[i..i + BLOCK_LEN]
+            {
+                let len = source[offset..]
+                    .iter()
+                    .zip(&target[i..])
+                    .take_while(|(a, b)| a == b)
+                    .count();
+
+                encoder.flush_literal(target, i);
+                encoder.action(SOURCE_COPY, len);
+
+                let delta = offset as i64 - encoder.source_relative as i64;
+                write_number(
+                    &mut encoder.patch,
+                    (delta.unsigned_abs() << 1) | (delta < 0) as u64,
+                );
+                encoder.source_relative = offset + len;
+
+                i += len;
+                encoder.literal_start = i;
+                hash = None;
+                continue;
+            }
+
+            hash = (i + BLOCK_LEN < target.len()).then(|| {
+                h.wrapping_sub((target[i] as u64).wrapping_mul(out_weight))
+                    .wrapping_mul(HASH_BASE)
+                    .wrapping_add(target[i + BLOCK_LEN] as u64)
+            });
+        }
+
+        i += 1;
+    }
+
+    encoder.flush_literal(target, target.len());
+
+    let mut patch = encoder.patch;
+    patch.extend_from_slice(&crc32fast::hash(source).to_le_bytes());
+    patch.extend_from_slice(&crc32fast::hash(target).to_le_bytes());
+
+    let patch_crc = crc32fast::hash(&patch);
+    patch.extend_from_slice(&patch_crc.to_le_bytes());
+
+    patch
+}
+
+pub fn is_bps(patch: &[u8]) -> bool {
+    patch.starts_with(MAGIC)
+}
+
+fn crc_at(patch: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([
+        patch[offset],
+        patch[offset + 1],
+        patch[offset + 2],
+        patch[offset + 3],
+    ])
+}
+
+/// Applies a BPS patch to `source`, checking all three checksums
+pub fn apply_bps(source: &[u8], patch: &[u8]) -> anyhow::Result<Vec<u8>> {
+    if patch.len() < MAGIC.len() + 12 || !is_bps(patch) {
+        anyhow::bail!("not a BPS patch");
+    }
+
+    let footer = patch.len() - 12;
+
+    if crc32fast::hash(&patch[..footer + 8]) != crc_at(patch, footer + 8) {
+        anyhow::bail!("BPS patch is corrupt (checksum mismatch)");
+    }
+
+    if crc32fast::hash(source) != crc_at(patch, footer) {
+        anyhow::bail!("source doesn't match the BPS source checksum, wrong original?");
+    }
+
+    let mut cursor = MAGIC.len();
+
+    let source_len = read_number(patch, &mut cursor)? as usize;
+    let target_len = read_number(patch, &mut cursor)? as usize;
+    let metadata_len = read_number(patch, &mut cursor)? as usize;
+    cursor = cursor
+        .checked_add(metadata_len)
+        .filter(|&cursor| cursor <= footer)
+        .ok_or_else(|| anyhow::anyhow!("BPS metadata runs past the end of the patch"))?;
+
+    if source_len != source.len() {
+        anyhow::bail!(
+            "BPS patch expects a {source_len} byte source, got {} bytes",
+            source.len()
+        );
+    }
+
+    // target_len is only trusted once the actions get there
+    let mut target = Vec::with_capacity(target_len.min(source.len() + patch.len()));
+    let mut source_relative = 0i64;
+    let mut target_relative = 0i64;
+
+    let out_of_range = || anyhow::anyhow!("BPS action reads out of range");
+
+    // start..start + len, or None when it overflows
+    let range = |start: usize, len: usize| Some(start..start.checked_add(len)?);
+
+    while cursor < footer {
+        let data = read_number(patch, &mut cursor)?;
+        let len = (data >> 2) as usize + 1;
+
+        if target
+            .len()
+            .checked_add(len)
+            .is_none_or(|end| end > target_len)
+        {
+            anyhow::bail!("BPS action writes past the {target_len} byte target");
+        }
+
+        match data & 3 {
+            SOURCE_READ => {
+                let bytes = range(target.len(), len)
+                    .and_then(|range| source.get(range))
+                    .ok_or_else(out_of_range)?;
+                target.extend_from_slice(bytes);
+            }
+            TARGET_READ => {
+                let bytes = range(cursor, len)
+                    .filter(|range| range.end <= footer)
+                    .and_then(|range| patch.get(range))
+                    .ok_or_else(out_of_range)?;
+                target.extend_from_slice(bytes);
+                cursor += len;
+            }
+            command => {
+                let offset = read_number(patch, &mut cursor)?;
+                let delta = (offset >> 1) as i64 * if offset & 1 != 0 { -1 } else { 1 };
+
+                if command == SOURCE_COPY {
+                    source_relative = source_relative
+                        .checked_add(delta)
+                        .ok_or_else(out_of_range)?;
+                    let start = usize::try_from(source_relative).map_err(|_| out_of_range())?;
+                    let bytes = range(start, len)
+                        .and_then(|range| source.get(range))
+                        .ok_or_else(out_of_range)?;
+                    target.extend_from_slice(bytes);
+                    source_relative += len as i64;
+                } else {
+                    debug_assert_eq!(command, TARGET_COPY);
+
+                    target_relative = target_relative
+                        .checked_add(delta)
+                        .ok_or_else(out_of_range)?;
+                    let start = usize::try_from(target_relative).map_err(|_| out_of_range())?;
+                    if start >= target.len() {
+                        return Err(out_of_range());
+                    }
+                    // may overlap the bytes being written, copy one at a time
+                    for j in start..start + len {
+                        target.push(target[j]);
+                    }
+                    target_relative += len as i64;
+                }
+            }
+        }
+    }
+
+    if target.len() != target_len {
+        anyhow::bail!(
+            "BPS patch produced {} bytes, expected {target_len}",
+            target.len()
+        );
+    }
+
+    if crc32fast::hash(&target) != crc_at(patch, footer + 4) {
+        anyhow::bail!("patched image doesn't match the BPS target checksum");
+    }
+
+    Ok(target)
+}
@@ -0,0 +1,52 @@
+use std::{fs, path::PathBuf};
+
+use crate::{
+    bps::{apply_bps, is_bps, make_bps},
+    ppf::{apply_ppf, is_ppf, make_ppf},
+};
+
+pub mod bps;
+pub mod ppf;
+
+/// Writes a patch from `original` to `modded`, the format follows the
+/// extension of `patch_file` (.ppf or .bps)
+pub fn make_patch(
+    original: PathBuf,
+    modded: PathBuf,
+    patch_file: PathBuf,
+    description: &str,
+) -> anyhow::Result<()> {
+    let original = fs::read(original)?;
+    let modded = fs::read(modded)?;
+
+    let patch = match patch_file.extension().and_then(|e| e.to_str()) {
+        Some("ppf") => make_ppf(&original, &modded, description)?,
+        Some("bps") => make_bps(&original, &modded),
+        _ => anyhow::bail!(
+            "{}: patch files must end in .ppf or .bps",
+            patch_file.display()
+        ),
+    };
+
+    fs::write(patch_file, patch)?;
+
+    Ok(())
+}
+
+/// Applies a PPF3 or BPS patch to `original`, writing the result to `output`
+pub fn apply_patch(original: PathBuf, patch_file: PathBuf, output: PathBuf) -> anyhow::Result<()> {
+    let mut image = fs::read(original)?;
+    let patch = fs::read(&patch_file)?;
+
+    if is_ppf(&patch) {
+        apply_ppf(&mut image, &patch)?;
+    } else if is_bps(&patch) {
+        image = apply_bps(&image, &patch)?;
+    } else {
+        anyhow::bail!("{} is not a PPF3 or BPS patch", patch_file.display());
+    }
+
+    fs::write(output, image)?;
+
+    Ok(())
+}
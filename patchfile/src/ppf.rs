@@ -0,0 +1,140 @@
+const MAGIC: &[u8; 5] = b"PPF30";
+const HEADER_LEN: usize = 60;
+const DESCRIPTION_LEN: usize = 50;
+
+/// Raw BIN images are checked against the sector holding the PVD copy
+const BLOCK_CHECK_OFFSET: usize = 0x9320;
+const BLOCK_CHECK_LEN: usize = 1024;
+
+const FILE_ID_BEGIN: &[u8] = b"@BEGIN_FILE_ID.DIZ";
+
+/// Records hold at most 255 bytes
+const MAX_RECORD_LEN: usize = 255;
+
+/// Largest image a record may grow, a 99 minute CD of 2352 byte sectors
+const MAX_IMAGE_LEN: usize = 99 * 60 * 75 * 2352;
+
+/// Builds a PPF3 patch turning `original` into `modded`. PPF can grow an
+/// image but can't truncate it.
+pub fn make_ppf(original: &[u8], modded: &[u8], description: &str) -> anyhow::Result<Vec<u8>> {
+    if modded.len() < original.len() {
+        anyhow::bail!(
+            "PPF can't shrink an image ({} -> {} bytes), use BPS instead",
+            original.len(),
+            modded.len()
+        );
+    }
+
+    let block_check = original.len() >= BLOCK_CHECK_OFFSET + BLOCK_CHECK_LEN;
+
+    let mut patch = Vec::with_capacity(HEADER_LEN);
+    patch.extend_from_slice(MAGIC);
+    // encoding method, 2 for PPF3
+    patch.push(2);
+
+    let mut desc = [b' '; DESCRIPTION_LEN];
+    let desc_len = description.len().min(DESCRIPTION_LEN);
+    desc[..desc_len].copy_from_slice(&description.as_bytes()[..desc_len]);
+    patch.extend_from_slice(&desc);
+
+    // image type BIN, block check, no undo data, dummy
+    patch.extend_from_slice(&[0, block_check as u8, 0, 0]);
+
+    if block_check {
+        patch
+            .extend_from_slice(&original[BLOCK_CHECK_OFFSET..BLOCK_CHECK_OFFSET + BLOCK_CHECK_LEN]);
+    }
+
+    let differs = |i: usize| original.get(i) != Some(&modded[i]);
+
+    let mut i = 0;
+    while i < modded.len() {
+        if !differs(i) {
+            i += 1;
+            continue;
+        }
+
+        let start = i;
+        while i < modded.len() && i - start < MAX_RECORD_LEN && differs(i) {
+            i += 1;
+        }
+
+        patch.extend_from_slice(&(start as u64).to_le_bytes());
+        patch.push((i - start) as u8);
+        patch.extend_from_slice(&modded[start..i]);
+    }
+
+    Ok(patch)
+}
+
+pub fn is_ppf(patch: &[u8]) -> bool {
+    patch.starts_with(MAGIC)
+}
+
+/// Applies a PPF3 patch to `image` in place
+pub fn apply_ppf(image: &mut Vec<u8>, patch: &[u8]) -> anyhow::Result<()> {
+    if patch.len() < HEADER_LEN || !is_ppf(patch) {
+        anyhow::bail!("not a PPF3 patch");
+    }
+
+    let image_type = patch[56];
+    let block_check = patch[57] != 0;
+    let undo = patch[58] != 0;
+
+    let mut cursor = HEADER_LEN;
+
+    if block_check {
+        let block = patch
+            .get(cursor..cursor + BLOCK_CHECK_LEN)
+            .ok_or_else(|| anyhow::anyhow!("PPF patch ends inside its block check"))?;
+
+        // GI images keep the check block at 0x80a0
+        let offset = if image_type == 1 {
+            0x80a0
+        } else {
+            BLOCK_CHECK_OFFSET
+        };
+
+        if image.get(offset..offset + BLOCK_CHECK_LEN) != Some(block) {
+            anyhow::bail!("image doesn't match the PPF block check, wrong original?");
+        }
+
+        cursor += BLOCK_CHECK_LEN;
+    }
+
+    while cursor < patch.len() {
+        if patch[cursor..].starts_with(FILE_ID_BEGIN) {
+            break;
+        }
+
+        let header = patch
+            .get(cursor..cursor + 9)
+            .ok_or_else(|| anyhow::anyhow!("truncated PPF record at 0x{cursor:x}"))?;
+
+        let offset = u64::from_le_bytes(header[..8].try_into()?) as usize;
+        let len = header[8] as usize;
+
+        let end = offset
+            .checked_add(len)
+            .filter(|&end| end <= MAX_IMAGE_LEN)
+            .ok_or_else(|| {
+                anyhow::anyhow!("PPF record at 0x{cursor:x} writes past the largest CD image")
+            })?;
+
+        // undo data follows the new bytes
+        let record_len = if undo { 9 + 2 * len } else { 9 + len };
+        if cursor + record_len > patch.len() {
+            anyhow::bail!("truncated PPF record at 0x{cursor:x}");
+        }
+
+        let data = &patch[cursor + 9..cursor + 9 + len];
+        cursor += record_len;
+
+        if image.len() < end {
+            image.resize(end, 0);
+        }
+        image[offset..end].copy_from_slice(data);
+    }
+
+    Ok(())
+}
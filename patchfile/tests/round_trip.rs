@@ -0,0 +1,151 @@
+use patchfile::{
+    bps::{apply_bps, make_bps},
+    ppf::{apply_ppf, make_ppf},
+};
+use proptest::prelude::*;
+
+/// An image and an edited copy: bytes changed, inserted and removed, so
+/// data also moves around
+fn image_pair() -> impl Strategy<Value = (Vec<u8>, Vec<u8>)> {
+    (
+        prop::collection::vec(any::<u8>(), 0..0x4000),
+        prop::collection::vec((any::<prop::sample::Index>(), 0..3u8, any::<u8>()), 0..16),
+    )
+        .prop_map(|(original, edits)| {
+            let mut modded = original.clone();
+
+            for (index, kind, byte) in edits {
+                if modded.is_empty() {
+                    modded.push(byte);
+                    continue;
+                }
+
+                let i = index.index(modded.len());
+                match kind {
+                    0 => modded[i] = byte,
+                    1 => modded.splice(i..i, vec![byte; 300]).for_each(drop),
+                    _ => modded.drain(i..(i + 300).min(modded.len())).for_each(drop),
+                }
+            }
+
+            (original, modded)
+        })
+}
+
+/// BPS numbers as make_bps writes them
+fn bps_number(patch: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let x = (value & 0x7f) as u8;
+        value >>= 7;
+
+        if value == 0 {
+            patch.push(0x80 | x);
+            break;
+        }
+
+        patch.push(x);
+        value -= 1;
+    }
+}
+
+/// A BPS patch of `actions` with valid checksums, so only the actions
+/// themselves can be rejected
+fn bps_patch(source: &[u8], target_len: u64, actions: &[u64]) -> Vec<u8> {
+    let mut patch = b"BPS1".to_vec();
+    bps_number(&mut patch, source.len() as u64);
+    bps_number(&mut patch, target_len);
+    bps_number(&mut patch, 0);
+    actions
+        .iter()
+        .for_each(|action| bps_number(&mut patch, *action));
+
+    patch.extend_from_slice(&crc32fast::hash(source).to_le_bytes());
+    patch.extend_from_slice(&0u32.to_le_bytes());
+    let patch_crc = crc32fast::hash(&patch);
+    patch.extend_from_slice(&patch_crc.to_le_bytes());
+
+    patch
+}
+
+proptest! {
+    #[test]
+    fn bps_round_trip((original, modded) in image_pair()) {
+        let patch = make_bps(&original, &modded);
+
+        prop_assert_eq!(apply_bps(&original, &patch).unwrap(), modded);
+    }
+
+    #[test]
+    fn ppf_round_trip((original, mut modded) in image_pair()) {
+        // PPF can't shrink an image
+        if modded.len() < original.len() {
+            modded.resize(original.len(), 0);
+        }
+
+        let patch = make_ppf(&original, &modded, "round trip").unwrap();
+
+        let mut image = original.clone();
+        apply_ppf(&mut image, &patch).unwrap();
+
+        prop_assert_eq!(image, modded);
+    }
+
+    #[test]
+    fn corrupt_bps_is_rejected((original, modded) in image_pair(), index: prop::sample::Index) {
+        let mut patch = make_bps(&original, &modded);
+        let i = index.index(patch.len());
+        patch[i] ^= 0x55;
+
+        prop_assert!(apply_bps(&original, &patch).is_err());
+    }
+
+    #[test]
+    fn truncated_bps_is_rejected((original, modded) in image_pair(), index: prop::sample::Index) {
+        let patch = make_bps(&original, &modded);
+        let len = index.index(patch.len());
+
+        prop_assert!(apply_bps(&original, &patch[..len]).is_err());
+    }
+
+    #[test]
+    fn truncated_ppf_record_is_rejected(original in prop::collection::vec(any::<u8>(), 1..4096)) {
+        let modded = original.iter().map(|b| !b).collect::<Vec<_>>();
+        let patch = make_ppf(&original, &modded, "").unwrap();
+
+        // cut inside the last record's data
+        let mut image = original.clone();
+        prop_assert!(apply_ppf(&mut image, &patch[..patch.len() - 1]).is_err());
+    }
+}
+
+#[test]
+fn bps_actions_past_the_target_size_are_rejected() {
+    let source = [1, 2, 3, 4];
+
+    // SourceRead of 4 bytes into a 2 byte target
+    let patch = bps_patch(&source, 2, &[3 << 2]);
+    assert!(apply_bps(&source, &patch).is_err());
+
+    // a TargetCopy that would repeat the first byte for ever
+    let patch = bps_patch(&source, 8, &[0, (u64::MAX >> 3) << 2 | 3, 0]);
+    assert!(apply_bps(&source, &patch).is_err());
+}
+
+#[test]
+fn ppf_records_past_a_cd_image_are_rejected() {
+    let mut patch = make_ppf(&[0; 16], &[0; 16], "").unwrap();
+    patch.extend_from_slice(&(u64::MAX - 1).to_le_bytes());
+    patch.push(4);
+    patch.extend_from_slice(&[1, 2, 3, 4]);
+
+    let mut image = vec![0; 16];
+    assert!(apply_ppf(&mut image, &patch).is_err());
+
+    let mut patch = make_ppf(&[0; 16], &[0; 16], "").unwrap();
+    patch.extend_from_slice(&(1u64 << 40).to_le_bytes());
+    patch.push(1);
+    patch.push(1);
+
+    assert!(apply_ppf(&mut image, &patch).is_err());
+    assert_eq!(image.len(), 16);
+}
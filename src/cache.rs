@@ -0,0 +1,176 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs::{self, File},
+    io::copy,
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+#[derive(Serialize, Deserialize, Clone)]
+struct FileHash {
+    len: u64,
+    /// Modification time in nanoseconds, a file is only rehashed when its
+    /// size or modification time changes
+    modified: u128,
+    sha256: String,
+}
+
+/// Inputs an artifact was last built from and what came out
+#[derive(Serialize, Deserialize, Clone)]
+struct Artifact {
+    inputs: String,
+    output: String,
+}
+
+/// Content hashes of source files and derived artifacts, kept between
+/// repacks so unchanged outputs can be reused
+#[derive(Serialize, Deserialize, Default)]
+pub struct BuildCache {
+    files: BTreeMap<PathBuf, FileHash>,
+    artifacts: BTreeMap<PathBuf, Artifact>,
+}
+
+impl BuildCache {
+    /// A missing or unreadable cache starts empty, everything gets rebuilt
+    pub fn load(cache_file: &Path) -> Self {
+        File::open(cache_file)
+            .ok()
+            .and_then(|file| serde_json::from_reader(file).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, cache_file: &Path) -> anyhow::Result<()> {
+        serde_json::to_writer(File::create(cache_file)?, self)?;
+
+        Ok(())
+    }
+
+    /// Every file hashed so far, the sources repack read included
+    pub fn files(&self) -> impl Iterator<Item = &Path> {
+        self.files.keys().map(PathBuf::as_path)
+    }
+
+    pub fn hash_file(&mut self, path: &Path) -> anyhow::Result<String> {
+        let metadata = fs::metadata(path)?;
+        let len = metadata.len();
+        let modified = metadata.modified()?.duration_since(UNIX_EPOCH)?.as_nanos();
+
+        if let Some(cached) = self.files.get(path)
+            && cached.len == len
+            && cached.modified == modified
+        {
+            return Ok(cached.sha256.clone());
+        }
+
+        let mut hasher = Sha256::new();
+        copy(&mut File::open(path)?, &mut hasher)?;
+        let sha256 = format!("{:x}", hasher.finalize());
+
+        self.files.insert(
+            path.to_path_buf(),
+            FileHash {
+                len,
+                modified,
+                sha256: sha256.clone(),
+            },
+        );
+
+        Ok(sha256)
+    }
+
+    /// Combined hash of a list of files, their names and order included
+    pub fn hash_files(&mut self, paths: &[PathBuf]) -> anyhow::Result<String> {
+        let mut hasher = Sha256::new();
+
+        for path in paths {
+            let sha256 = self.hash_file(path)?;
+
+            hasher.update(path.to_string_lossy().as_bytes());
+            hasher.update([0]);
+            hasher.update(sha256.as_bytes());
+        }
+
+        Ok(format!("{:x}", hasher.finalize()))
+    }
+
+    /// Whether `output` still holds what was built from `inputs` last time
+    pub fn is_fresh(&mut self, output: &Path, inputs: &str) -> anyhow::Result<bool> {
+        let Some(artifact) = self.artifacts.get(output).cloned() else {
+            return Ok(false);
+        };
+
+        Ok(artifact.inputs == inputs
+            && output.is_file()
+            && self.hash_file(output)? == artifact.output)
+    }
+
+    pub fn record(&mut self, output: &Path, inputs: String) -> anyhow::Result<()> {
+        let output_hash = self.hash_file(output)?;
+
+        self.artifacts.insert(
+            output.to_path_buf(),
+            Artifact {
+                inputs,
+                output: output_hash,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Makes `dst` a copy of `src`, only copying files whose content differs
+    /// and removing files `src` doesn't have. Recorded artifacts are left
+    /// alone, they're rebuilt from their inputs instead.
+    pub fn sync_dir(&mut self, src: &Path, dst: &Path) -> anyhow::Result<()> {
+        fs::create_dir_all(dst)?;
+
+        let mut names = BTreeSet::new();
+
+        for entry in fs::read_dir(src)? {
+            let entry = entry?;
+            let src_path = entry.path();
+            let dst_path = dst.join(entry.file_name());
+
+            names.insert(entry.file_name());
+
+            if entry.file_type()?.is_dir() {
+                if dst_path.is_file() {
+                    fs::remove_file(&dst_path)?;
+                }
+
+                self.sync_dir(&src_path, &dst_path)?;
+            } else {
+                if self.artifacts.contains_key(&dst_path) {
+                    continue;
+                }
+
+                if dst_path.is_dir() {
+                    fs::remove_dir_all(&dst_path)?;
+                }
+
+                if !dst_path.is_file() || self.hash_file(&src_path)? != self.hash_file(&dst_path)? {
+                    fs::copy(&src_path, &dst_path)?;
+                }
+            }
+        }
+
+        for entry in fs::read_dir(dst)? {
+            let entry = entry?;
+
+            if names.contains(&entry.file_name()) {
+                continue;
+            }
+
+            if entry.file_type()?.is_dir() {
+                fs::remove_dir_all(entry.path())?;
+            } else {
+                fs::remove_file(entry.path())?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,114 @@
+//! Unpack and repack pipeline behind the s2 command line, for tools that
+//! drive it without spawning the binary
+
+use std::path::PathBuf;
+
+use mods::Conflict;
+
+pub mod cache;
+pub mod mods;
+pub mod project;
+pub mod repack;
+pub mod unpack;
+pub mod watch;
+
+/// Level files in WAD order, starting at WAD entry 14
+pub const LEVELS: [&str; 58] = [
+    "level_10_summer_forest_code.ovl",
+    "level_10_summer_forest_data.dat",
+    "level_11_glimmer_code.ovl",
+    "level_11_glimmer_data.dat",
+    "level_12_idol_springs_code.ovl",
+    "level_12_idol_springs_data.dat",
+    "level_13_colossus_code.ovl",
+    "level_13_colossus_data.dat",
+    "level_21_hurricos_code.ovl",
+    "level_21_hurricos_data.dat",
+    "level_22_aquaria_towers_code.ovl",
+    "level_22_aquaria_towers_data.dat",
+    "level_23_sunny_beach_code.ovl",
+    "level_23_sunny_beach_data.dat",
+    "level_25_ocean_speedway_code.ovl",
+    "level_25_ocean_speedway_data.dat",
+    "level_26_crushs_dungeon_code.ovl",
+    "level_26_crushs_dungeon_data.dat",
+    "level_30_autumn_plains_code.ovl",
+    "level_30_autumn_plains_data.dat",
+    "level_31_skelos_badlands_code.ovl",
+    "level_31_skelos_badlands_data.dat",
+    "level_32_crystal_glacier_code.ovl",
+    "level_32_crystal_glacier_data.dat",
+    "level_33_breeze_harbor_code.ovl",
+    "level_33_breeze_harbor_data.dat",
+    "level_34_zephyr_code.ovl",
+    "level_34_zephyr_data.dat",
+    "level_35_metro_speedway_code.ovl",
+    "level_35_metro_speedway_data.dat",
+    "level_41_scorch_code.ovl",
+    "level_41_scorch_data.dat",
+    "level_42_shady_oasis_code.ovl",
+    "level_42_shady_oasis_data.dat",
+    "level_43_magma_cone_code.ovl",
+    "level_43_magma_cone_data.dat",
+    "level_44_fracture_hills_code.ovl",
+    "level_44_fracture_hills_data.dat",
+    "level_45_icy_speedway_code.ovl",
+    "level_45_icy_speedway_data.dat",
+    "level_46_gulps_overlook_code.ovl",
+    "level_46_gulps_overlook_data.dat",
+    "level_50_winter_tundra_code.ovl",
+    "level_50_winter_tundra_data.dat",
+    "level_51_mystic_marsh_code.ovl",
+    "level_51_mystic_marsh_data.dat",
+    "level_52_cloud_temples_code.ovl",
+    "level_52_cloud_temples_data.dat",
+    "level_55_canyon_speedway_code.ovl",
+    "level_55_canyon_speedway_data.dat",
+    "level_61_robotica_farms_code.ovl",
+    "level_61_robotica_farms_data.dat",
+    "level_62_metropolis_code.ovl",
+    "level_62_metropolis_data.dat",
+    "level_65_dragon_shores_code.ovl",
+    "level_65_dragon_shores_data.dat",
+    "level_66_riptos_arena_code.ovl",
+    "level_66_riptos_arena_data.dat",
+];
+
+/// A code patch written into a target file
+#[derive(Clone, Debug)]
+pub struct AppliedPatch {
+    /// Name of the patch file's set
+    pub set: String,
+    pub target: String,
+    pub address: String,
+    /// Instructions written
+    pub words: usize,
+}
+
+/// What Project::unpack and Project::repack are doing, reported as it
+/// happens
+#[derive(Clone, Debug)]
+pub enum Progress {
+    /// A step of the pipeline started
+    Step(&'static str),
+    /// A level finished extracting, `done` out of `total`
+    Level {
+        name: &'static str,
+        done: usize,
+        total: usize,
+    },
+    Patch(AppliedPatch),
+    Conflict(Conflict),
+    /// A mod is about to be applied
+    Mod(String),
+    /// An output's inputs didn't change, it was kept
+    UpToDate(PathBuf),
+    /// Something was skipped without failing the step
+    Warning(String),
+    /// Watch is waiting for changes in a directory
+    Watching(PathBuf),
+    /// A watched file changed, a rebuild starts
+    Changed(PathBuf),
+    /// A watched rebuild failed, watching goes on
+    RebuildFailed(String),
+}
@@ -1,177 +1,221 @@
-use std::{
-    fs::{self, File},
-    path::PathBuf,
-    process::Command,
-};
+use std::path::PathBuf;
 
 use clap::{Parser, Subcommand};
-use collision::parse_collision;
-use level::parse_level;
-use wad::{parse_wad, rebuild_wad};
+use exe::Exe;
+use mips::overlay::Overlay;
+use patchfile::{apply_patch, make_patch};
+use s2::{Progress, project::Project, watch::watch};
 
 #[derive(Parser, Debug)]
 struct Args {
+    /// Project directory, defaults to the closest one holding an s2.json
+    #[arg(long, global = true)]
+    project: Option<PathBuf>,
     #[command(subcommand)]
     command: SubCommand,
 }
 
 #[derive(Subcommand, Debug)]
 enum SubCommand {
+    /// Create a project directory
+    Init {
+        /// Project directory
+        dir: PathBuf,
+        /// Game bin file to unpack
+        #[arg(long)]
+        bin: Option<PathBuf>,
+        #[arg(long, default_value = "NTSC-U")]
+        region: String,
+    },
     /// Unpack and prepare
     Unpack {
-        /// Target game bin file
-        target_bin: PathBuf,
+        /// Target game bin file, defaults to the project's
+        target_bin: Option<PathBuf>,
+        /// Levels extracted in parallel, defaults to one per CPU
+        #[arg(short, long)]
+        jobs: Option<usize>,
     },
     /// Rebuild iso
     Repack {
         /// Output folder
         name: String,
+        /// Mod directories with a mod.json, applied on a copy of the extract
+        /// directory from low to high priority. Mods that edit the same data
+        /// need different priorities.
+        #[arg(long = "mod")]
+        mods: Vec<PathBuf>,
+        /// Ignore the build cache and rebuild everything
+        #[arg(long)]
+        clean: bool,
+    },
+    /// Repack whenever a texture, sound, patch, manifest or other repack
+    /// input changes
+    Watch {
+        /// Output folder
+        name: String,
+        /// Mod directories with a mod.json, also watched
+        #[arg(long = "mod")]
+        mods: Vec<PathBuf>,
+    },
+    /// Create a PPF3 or BPS patch from the original and a rebuilt image
+    MakePatch {
+        /// Original game bin file
+        original: PathBuf,
+        /// Rebuilt bin file
+        modded: PathBuf,
+        /// Patch file to write, .ppf or .bps
+        patch: PathBuf,
+        /// Description stored in PPF patches
+        #[arg(long, default_value = "")]
+        description: String,
+    },
+    /// Apply a PPF3 or BPS patch to the original image
+    ApplyPatch {
+        /// Original game bin file
+        original: PathBuf,
+        /// Patch file
+        patch: PathBuf,
+        /// Patched bin file to write
+        output: PathBuf,
+    },
+    /// Disassemble a code overlay
+    Disasm {
+        /// Overlay file
+        overlay: PathBuf,
+        /// RAM address the overlay is loaded at (hex)
+        #[arg(long, value_parser = parse_hex, default_value = "80073800")]
+        base: u32,
+    },
+    /// Inspect or modify the main executable
+    Exe {
+        /// PS-X EXE file
+        exe: PathBuf,
+        #[command(subcommand)]
+        command: ExeCommand,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum ExeCommand {
+    /// Print the header
+    Info,
+    /// Grow the text segment to make room for a code cave
+    Grow {
+        /// Bytes to add, rounded up to a whole sector
+        bytes: u32,
     },
 }
 
-const LEVELS: [&str; 58] = [
-    "level_10_summer_forest_code.ovl",
-    "level_10_summer_forest_data.dat",
-    "level_11_glimmer_code.ovl",
-    "level_11_glimmer_data.dat",
-    "level_12_idol_springs_code.ovl",
-    "level_12_idol_springs_data.dat",
-    "level_13_colossus_code.ovl",
-    "level_13_colossus_data.dat",
-    "level_21_hurricos_code.ovl",
-    "level_21_hurricos_data.dat",
-    "level_22_aquaria_towers_code.ovl",
-    "level_22_aquaria_towers_data.dat",
-    "level_23_sunny_beach_code.ovl",
-    "level_23_sunny_beach_data.dat",
-    "level_25_ocean_speedway_code.ovl",
-    "level_25_ocean_speedway_data.dat",
-    "level_26_crushs_dungeon_code.ovl",
-    "level_26_crushs_dungeon_data.dat",
-    "level_30_autumn_plains_code.ovl",
-    "level_30_autumn_plains_data.dat",
-    "level_31_skelos_badlands_code.ovl",
-    "level_31_skelos_badlands_data.dat",
-    "level_32_crystal_glacier_code.ovl",
-    "level_32_crystal_glacier_data.dat",
-    "level_33_breeze_harbor_code.ovl",
-    "level_33_breeze_harbor_data.dat",
-    "level_34_zephyr_code.ovl",
-    "level_34_zephyr_data.dat",
-    "level_35_metro_speedway_code.ovl",
-    "level_35_metro_speedway_data.dat",
-    "level_41_scorch_code.ovl",
-    "level_41_scorch_data.dat",
-    "level_42_shady_oasis_code.ovl",
-    "level_42_shady_oasis_data.dat",
-    "level_43_magma_cone_code.ovl",
-    "level_43_magma_cone_data.dat",
-    "level_44_fracture_hills_code.ovl",
-    "level_44_fracture_hills_data.dat",
-    "level_45_icy_speedway_code.ovl",
-    "level_45_icy_speedway_data.dat",
-    "level_46_gulps_overlook_code.ovl",
-    "level_46_gulps_overlook_data.dat",
-    "level_50_winter_tundra_code.ovl",
-    "level_50_winter_tundra_data.dat",
-    "level_51_mystic_marsh_code.ovl",
-    "level_51_mystic_marsh_data.dat",
-    "level_52_cloud_temples_code.ovl",
-    "level_52_cloud_temples_data.dat",
-    "level_55_canyon_speedway_code.ovl",
-    "level_55_canyon_speedway_data.dat",
-    "level_61_robotica_farms_code.ovl",
-    "level_61_robotica_farms_data.dat",
-    "level_62_metropolis_code.ovl",
-    "level_62_metropolis_data.dat",
-    "level_65_dragon_shores_code.ovl",
-    "level_65_dragon_shores_data.dat",
-    "level_66_riptos_arena_code.ovl",
-    "level_66_riptos_arena_data.dat",
-];
+fn parse_hex(value: &str) -> Result<u32, std::num::ParseIntError> {
+    u32::from_str_radix(value.trim_start_matches("0x"), 16)
+}
+
+/// Prints pipeline progress the way s2 always has
+fn print_progress(progress: Progress) {
+    match progress {
+        Progress::Step(step) => println!("{step}"),
+        // levels finish in any order, the step line is enough
+        Progress::Level { .. } => {}
+        Progress::Patch(patch) => println!(
+            "  {}: {} instructions at {} in {}",
+            patch.set, patch.words, patch.address, patch.target
+        ),
+        Progress::Conflict(conflict) => {
+            println!(
+                "  conflict: {} and {} both edit {}",
+                conflict.first, conflict.second, conflict.edit
+            );
+
+            if let Some(winner) = &conflict.winner {
+                println!("    {winner} wins by priority");
+            }
+        }
+        Progress::Mod(name) => println!("Apply mod {name}"),
+        Progress::UpToDate(output) => println!("  {} up to date", output.display()),
+        Progress::Warning(warning) => eprintln!("warning: {warning}"),
+        Progress::Watching(dir) => println!("Watching {} for changes", dir.display()),
+        Progress::Changed(file) => println!("{} changed, rebuilding", file.display()),
+        Progress::RebuildFailed(error) => eprintln!("Rebuild failed: {error}"),
+    }
+}
 
 fn main() -> anyhow::Result<()> {
     let args = Args::parse();
 
     match args.command {
-        SubCommand::Unpack { target_bin } => {
-            println!("Unpacking ISO");
-            Command::new("dumpsxiso")
-                .args(["-x", "extract", "-s", "out.xml"])
-                .arg(target_bin)
-                .output()?;
-
-            println!("Unpacking main WAD file");
-            let wad_file = ["extract", "WAD.WAD"].iter().collect();
-            let output_dir = ["extract", "WAD"].iter().collect();
-            let mut manifest = parse_wad(wad_file, output_dir)?;
-
-            println!("Moving files");
-            let pb: PathBuf = ["extract", "WAD", "levels"].iter().collect();
-            fs::create_dir_all(pb)?;
-
-            println!("Handling levels");
-            for (i, wfile) in manifest.files.iter_mut().skip(14).take(29 * 2).enumerate() {
-                let mut new_name = wfile.clone();
-                new_name.pop();
-                new_name.push("levels");
-                new_name.push(LEVELS[i]);
-
-                fs::rename(&wfile, &new_name)?;
+        SubCommand::Init { dir, bin, region } => {
+            let project = Project::init(dir, bin, region)?;
 
-                *wfile = new_name;
-
-                if LEVELS[i].ends_with(".dat") {
-                    let mut output_dir = wfile.clone();
-                    output_dir.pop();
-                    output_dir.push(LEVELS[i].trim_end_matches(".dat"));
-
-                    let level_manifest = parse_level(wfile.clone(), output_dir.clone())?;
-
-                    let mut level_json = output_dir.clone();
-                    level_json.push("level.json");
-
-                    let level_json_f = File::create(level_json)?;
+            println!("Created project in {}", project.dir.display());
+        }
+        SubCommand::Unpack { target_bin, jobs } => {
+            let project = Project::discover(args.project)?;
 
-                    serde_json::to_writer_pretty(level_json_f, &level_manifest)?;
+            project.unpack(target_bin.as_deref(), jobs, &print_progress)?;
+        }
+        SubCommand::Repack { name, mods, clean } => {
+            let project = Project::discover(args.project)?;
 
-                    output_dir.push("colission");
+            project.repack(&name, &mods, clean, &print_progress)?;
+        }
+        SubCommand::Watch { name, mods } => {
+            let project = Project::discover(args.project)?;
+
+            watch(
+                &project,
+                &mods,
+                || {
+                    project.repack(&name, &mods, false, &print_progress)?;
+
+                    Ok(())
+                },
+                &print_progress,
+            )?;
+        }
+        SubCommand::MakePatch {
+            original,
+            modded,
+            patch,
+            description,
+        } => {
+            make_patch(original, modded, patch, &description)?;
+        }
+        SubCommand::ApplyPatch {
+            original,
+            patch,
+            output,
+        } => {
+            apply_patch(original, patch, output)?;
+        }
+        SubCommand::Disasm { overlay, base } => {
+            let overlay = Overlay::load(overlay, base)?;
 
-                    parse_collision(level_manifest.collision_data, output_dir.clone())?;
+            print!("{}", overlay.listing());
+        }
+        SubCommand::Exe { exe, command } => {
+            let mut executable = Exe::load(exe.clone())?;
+
+            match command {
+                ExeCommand::Info => {
+                    let header = executable.header;
+
+                    println!("pc0:    0x{:08x}", header.pc0);
+                    println!("gp0:    0x{:08x}", header.gp0);
+                    println!("text:   0x{:08x} + 0x{:x}", header.t_addr, header.t_size);
+                    println!("data:   0x{:08x} + 0x{:x}", header.d_addr, header.d_size);
+                    println!("bss:    0x{:08x} + 0x{:x}", header.b_addr, header.b_size);
+                    println!("stack:  0x{:08x} + 0x{:x}", header.s_addr, header.s_size);
+                }
+                ExeCommand::Grow { bytes } => {
+                    let cave = executable.grow(bytes)?;
+                    executable.save(exe)?;
+
+                    println!(
+                        "Code cave at 0x{cave:08x}..0x{:08x}",
+                        executable.header.t_addr as u64 + executable.header.t_size as u64
+                    );
                 }
             }
-
-            println!("Save WAD.WAD.json");
-            let was_wad_json =
-                File::create(["extract", "WAD.WAD.json"].iter().collect::<PathBuf>())?;
-
-            serde_json::to_writer_pretty(was_wad_json, &manifest)?;
-        }
-        SubCommand::Repack { name } => {
-            println!("Rebuild WAD.WAD");
-            let manifest_pbuf: PathBuf = ["extract", "WAD.WAD.json"].iter().collect();
-
-            let manifest_file = File::open(manifest_pbuf)?;
-
-            let manifest = serde_json::from_reader(manifest_file)?;
-
-            let wad_wad = ["extract", "WAD.WAD"].iter().collect();
-            rebuild_wad(manifest, wad_wad)?;
-
-            println!("Rebuild ISO");
-            // make sure folder exists
-            fs::create_dir_all("out")?;
-
-            let out_b: PathBuf = ["out", format!("{}.bin", name).as_str()].iter().collect();
-            let out_c: PathBuf = ["out", format!("{}.cue", name).as_str()].iter().collect();
-
-            Command::new("mkpsxiso")
-                .arg("-o")
-                .arg(out_b)
-                .arg("-c")
-                .arg(out_c)
-                .arg("out.xml")
-                .output()?;
         }
     }
 
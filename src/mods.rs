@@ -0,0 +1,459 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Display,
+    fs::{self, File, OpenOptions},
+    io::{Seek, Write},
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use collision::inject::replace_triangles;
+use level::inject::{TextureRect, replace_collision, replace_sound, replace_texture_page};
+use mips::patch::{Patch, Region};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TextureInjection {
+    /// Level name without the `_data.dat` suffix, e.g. `level_10_summer_forest`
+    pub level: String,
+    pub page: u8,
+    pub file: PathBuf,
+    /// Part of the page to copy from `file`, the whole page by default
+    #[serde(default)]
+    pub rect: Option<TextureRect>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SoundInjection {
+    pub level: String,
+    pub id: u16,
+    pub file: PathBuf,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CollisionOverride {
+    pub level: String,
+    pub file: PathBuf,
+    /// Triangles to copy from `file`, by default it replaces the whole
+    /// collision data
+    #[serde(default)]
+    pub triangles: Option<Range<u32>>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct BytePatch {
+    pub target: String,
+    /// RAM address, in hex
+    pub address: String,
+    /// Bytes to write, in hex
+    pub data: String,
+}
+
+/// mod.json, paths are relative to the mod directory
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ModManifest {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// Mods are applied from low to high priority, so on a conflict the
+    /// higher one wins. Repack refuses conflicts between equal priorities.
+    #[serde(default)]
+    pub priority: i32,
+    /// WAD entries to replace, by file name (`level_10_summer_forest_code.ovl`, `3.bin`)
+    #[serde(default)]
+    pub files: BTreeMap<String, PathBuf>,
+    #[serde(default)]
+    pub textures: Vec<TextureInjection>,
+    #[serde(default)]
+    pub sounds: Vec<SoundInjection>,
+    #[serde(default)]
+    pub collision: Vec<CollisionOverride>,
+    #[serde(default)]
+    pub patches: Vec<Patch>,
+    #[serde(default)]
+    pub bytes: Vec<BytePatch>,
+}
+
+impl ModManifest {
+    pub fn load(mod_dir: &Path) -> anyhow::Result<Self> {
+        let mod_json = mod_dir.join("mod.json");
+
+        let mut manifest: ModManifest = serde_json::from_reader(File::open(&mod_json)?)
+            .with_context(|| mod_json.display().to_string())?;
+
+        for file in manifest.files.values_mut() {
+            *file = mod_dir.join(&file);
+        }
+        for texture in manifest.textures.iter_mut() {
+            texture.file = mod_dir.join(&texture.file);
+        }
+        for sound in manifest.sounds.iter_mut() {
+            sound.file = mod_dir.join(&sound.file);
+        }
+        for collision in manifest.collision.iter_mut() {
+            collision.file = mod_dir.join(&collision.file);
+        }
+
+        Ok(manifest)
+    }
+
+    pub fn edits(&self) -> anyhow::Result<Vec<Edit>> {
+        let mut edits = Vec::new();
+
+        let level_edit = |level: &str, part| Edit {
+            file: level_data(level),
+            part: Some(part),
+        };
+
+        for name in self.files.keys() {
+            edits.push(Edit {
+                file: name.clone(),
+                part: None,
+            });
+        }
+
+        for texture in self.textures.iter() {
+            edits.push(level_edit(
+                &texture.level,
+                Part::Texture(texture.page, texture.rect.unwrap_or(TextureRect::PAGE)),
+            ));
+        }
+
+        for sound in self.sounds.iter() {
+            edits.push(level_edit(&sound.level, Part::Sound(sound.id)));
+        }
+
+        for collision in self.collision.iter() {
+            edits.push(level_edit(
+                &collision.level,
+                Part::Collision(collision.triangles.clone()),
+            ));
+        }
+
+        for patch in self.patches.iter() {
+            let address = patch.address()? as u64;
+            let len = patch.assemble()?.len() as u64 * 4;
+
+            edits.push(Edit {
+                file: patch.target.clone(),
+                part: Some(Part::Code(address..address + len)),
+            });
+        }
+
+        for byte_patch in self.bytes.iter() {
+            let address = byte_patch.address()? as u64;
+            let len = byte_patch.bytes()?.len() as u64;
+
+            edits.push(Edit {
+                file: byte_patch.target.clone(),
+                part: Some(Part::Code(address..address + len)),
+            });
+        }
+
+        Ok(edits)
+    }
+}
+
+/// Edits of different mods touching the same data. `mods` must be in the
+/// order they're applied.
+pub fn find_conflicts(mods: &[ModManifest]) -> anyhow::Result<Vec<Conflict>> {
+    let edits = mods
+        .iter()
+        .map(|m| m.edits())
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let mut conflicts = Vec::new();
+
+    for (i, first) in mods.iter().enumerate() {
+        for (j, second) in mods.iter().enumerate().skip(i + 1) {
+            for edit in edits[i].iter() {
+                for other in edits[j].iter().filter(|other| edit.overlaps(other)) {
+                    // report the narrower of the two edits
+                    let edit = if edit.part.is_none() { other } else { edit };
+
+                    conflicts.push(Conflict {
+                        first: first.name.clone(),
+                        second: second.name.clone(),
+                        edit: edit.clone(),
+                        winner: (first.priority != second.priority).then(|| second.name.clone()),
+                    });
+                }
+            }
+        }
+    }
+
+    Ok(conflicts)
+}
+
+/// Part of the extracted tree a mod writes to
+#[derive(Clone, Debug)]
+pub struct Edit {
+    /// WAD entry or SCUS_944.25
+    pub file: String,
+    /// None replaces the whole file
+    pub part: Option<Part>,
+}
+
+/// Data inside a file an edit writes to
+#[derive(Clone, Debug)]
+pub enum Part {
+    /// RAM addresses
+    Code(Range<u64>),
+    /// Sound id
+    Sound(u16),
+    /// Pixels of a texture page
+    Texture(u8, TextureRect),
+    /// Triangle indices, None replaces the whole collision data
+    Collision(Option<Range<u32>>),
+}
+
+fn ranges_overlap<T: PartialOrd>(range: &Range<T>, other: &Range<T>) -> bool {
+    range.start < other.end && other.start < range.end
+}
+
+impl Edit {
+    fn overlaps(&self, other: &Edit) -> bool {
+        if self.file != other.file {
+            return false;
+        }
+
+        let (Some(part), Some(other_part)) = (&self.part, &other.part) else {
+            return true;
+        };
+
+        match (part, other_part) {
+            (Part::Code(range), Part::Code(other_range)) => ranges_overlap(range, other_range),
+            (Part::Sound(id), Part::Sound(other_id)) => id == other_id,
+            (Part::Texture(page, rect), Part::Texture(other_page, other_rect)) => {
+                page == other_page && rect.overlaps(other_rect)
+            }
+            (Part::Collision(Some(range)), Part::Collision(Some(other_range))) => {
+                ranges_overlap(range, other_range)
+            }
+            (Part::Collision(_), Part::Collision(_)) => true,
+            _ => false,
+        }
+    }
+}
+
+impl Display for Edit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.part {
+            None => write!(f, "{}", self.file),
+            Some(Part::Code(range)) => write!(
+                f,
+                "{} 0x{:08x}..0x{:08x}",
+                self.file, range.start, range.end
+            ),
+            Some(Part::Sound(id)) => write!(f, "{} sound {id}", self.file),
+            Some(Part::Texture(page, rect)) => write!(
+                f,
+                "{} texture page {page} {}x{} at {},{}",
+                self.file, rect.width, rect.height, rect.x, rect.y
+            ),
+            Some(Part::Collision(None)) => write!(f, "{} collision", self.file),
+            Some(Part::Collision(Some(range))) => write!(
+                f,
+                "{} collision triangles {}..{}",
+                self.file, range.start, range.end
+            ),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Conflict {
+    pub first: String,
+    pub second: String,
+    pub edit: Edit,
+    /// Name of the mod whose edit ends up in the build, None on a tie
+    pub winner: Option<String>,
+}
+
+fn parse_hex_bytes(data: &str) -> anyhow::Result<Vec<u8>> {
+    let digits = data
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<Vec<_>>();
+
+    if digits.len() % 2 != 0 {
+        anyhow::bail!("odd number of hex digits in `{data}`");
+    }
+
+    digits
+        .chunks(2)
+        .map(|pair| {
+            let byte = pair.iter().collect::<String>();
+
+            u8::from_str_radix(&byte, 16).map_err(|_| anyhow::anyhow!("invalid hex byte `{byte}`"))
+        })
+        .collect()
+}
+
+impl BytePatch {
+    pub fn address(&self) -> anyhow::Result<u32> {
+        Ok(u32::from_str_radix(
+            self.address.trim().trim_start_matches("0x"),
+            16,
+        )?)
+    }
+
+    pub fn bytes(&self) -> anyhow::Result<Vec<u8>> {
+        parse_hex_bytes(&self.data)
+    }
+
+    pub fn apply(&self, target_file: PathBuf, region: Region) -> anyhow::Result<()> {
+        let address = self.address()?;
+        let bytes = self.bytes()?;
+
+        let mut file = OpenOptions::new().write(true).open(&target_file)?;
+
+        let offset = region.file_offset(address).ok_or_else(|| {
+            anyhow::anyhow!("0x{address:08x} is below the start of {}", self.target)
+        })?;
+
+        if offset + bytes.len() as u64 > file.metadata()?.len() {
+            anyhow::bail!(
+                "{} bytes at 0x{address:08x} run past the end of {}",
+                bytes.len(),
+                self.target
+            );
+        }
+
+        file.seek(std::io::SeekFrom::Start(offset))?;
+        file.write_all(&bytes)?;
+
+        Ok(())
+    }
+}
+
+/// WAD entry name of a level's data file
+fn level_data(level: &str) -> String {
+    format!("{level}_data.dat")
+}
+
+/// Files `manifest` feeds into `level`'s data file, mod.json first, or
+/// nothing when the mod leaves the level alone
+pub fn level_mod_inputs(manifest: &ModManifest, mod_dir: &Path, level: &str) -> Vec<PathBuf> {
+    let mut inputs: Vec<PathBuf> = manifest
+        .files
+        .get(&level_data(level))
+        .into_iter()
+        .cloned()
+        .chain(
+            manifest
+                .textures
+                .iter()
+                .filter(|texture| texture.level == level)
+                .map(|texture| texture.file.clone()),
+        )
+        .chain(
+            manifest
+                .sounds
+                .iter()
+                .filter(|sound| sound.level == level)
+                .map(|sound| sound.file.clone()),
+        )
+        .chain(
+            manifest
+                .collision
+                .iter()
+                .filter(|collision| collision.level == level)
+                .map(|collision| collision.file.clone()),
+        )
+        .collect();
+
+    if !inputs.is_empty() {
+        inputs.insert(0, mod_dir.join("mod.json"));
+    }
+
+    inputs
+}
+
+/// Applies the texture, sound and collision edits and the whole file
+/// replacement `manifest` makes to `level`, whose data file is `level_file`
+pub fn apply_level_mod(
+    manifest: &ModManifest,
+    level: &str,
+    level_file: &Path,
+) -> anyhow::Result<()> {
+    if let Some(file) = manifest.files.get(&level_data(level)) {
+        fs::copy(file, level_file)?;
+    }
+
+    for texture in manifest.textures.iter().filter(|t| t.level == level) {
+        replace_texture_page(
+            level_file.to_path_buf(),
+            texture.page,
+            &texture.file,
+            texture.rect.unwrap_or(TextureRect::PAGE),
+        )?;
+    }
+
+    for sound in manifest.sounds.iter().filter(|s| s.level == level) {
+        replace_sound(level_file.to_path_buf(), sound.id, &sound.file)?;
+    }
+
+    for collision in manifest.collision.iter().filter(|c| c.level == level) {
+        match &collision.triangles {
+            Some(triangles) => {
+                replace_triangles(level_file.to_path_buf(), &collision.file, triangles.clone())?
+            }
+            None => replace_collision(level_file.to_path_buf(), &collision.file)?,
+        }
+    }
+
+    Ok(())
+}
+
+/// Applies a mod to an extracted tree, except for level data edits which
+/// apply_level_mod makes. `patch_target` resolves a code target to its file
+/// and address mapping.
+pub fn apply_mod(
+    manifest: &ModManifest,
+    root: &Path,
+    patch_target: impl Fn(&str) -> anyhow::Result<(PathBuf, Region)>,
+) -> anyhow::Result<()> {
+    let wad_dir = root.join("WAD");
+    let levels_dir = wad_dir.join("levels");
+
+    for (name, file) in manifest.files.iter() {
+        let dst = [levels_dir.join(name), wad_dir.join(name)]
+            .into_iter()
+            .find(|dst| dst.is_file())
+            .ok_or_else(|| anyhow::anyhow!("{}: no WAD entry named `{name}`", manifest.name))?;
+
+        if name.ends_with("_data.dat") {
+            continue;
+        }
+
+        fs::copy(file, dst)?;
+    }
+
+    for level in manifest
+        .textures
+        .iter()
+        .map(|texture| &texture.level)
+        .chain(manifest.sounds.iter().map(|sound| &sound.level))
+        .chain(manifest.collision.iter().map(|collision| &collision.level))
+    {
+        if !levels_dir.join(level_data(level)).is_file() {
+            anyhow::bail!("{}: no level named `{level}`", manifest.name);
+        }
+    }
+
+    for patch in manifest.patches.iter() {
+        let (target_file, region) = patch_target(&patch.target)?;
+
+        patch.apply(target_file, region)?;
+    }
+
+    for byte_patch in manifest.bytes.iter() {
+        let (target_file, region) = patch_target(&byte_patch.target)?;
+
+        byte_patch.apply(target_file, region)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,129 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+pub const CONFIG_FILE: &str = "s2.json";
+/// Content hashes of the last repack
+pub const CACHE_FILE: &str = ".s2cache.json";
+
+/// s2.json, paths are relative to the project directory
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct ProjectConfig {
+    /// Game image Unpack extracts
+    pub source_bin: Option<PathBuf>,
+    pub region: String,
+    /// Main executable name on the disc, also the patch target name
+    pub exe: String,
+    pub extract_dir: PathBuf,
+    /// Copy of extract_dir patches and mods are applied to
+    pub build_dir: PathBuf,
+    pub out_dir: PathBuf,
+    /// mkpsxiso layout written by dumpsxiso
+    pub iso_xml: PathBuf,
+    pub patches_dir: PathBuf,
+}
+
+impl Default for ProjectConfig {
+    fn default() -> Self {
+        Self {
+            source_bin: None,
+            region: "NTSC-U".into(),
+            exe: "SCUS_944.25".into(),
+            extract_dir: "extract".into(),
+            build_dir: "build".into(),
+            out_dir: "out".into(),
+            iso_xml: "out.xml".into(),
+            patches_dir: "patches".into(),
+        }
+    }
+}
+
+pub struct Project {
+    /// Absolute project directory
+    pub dir: PathBuf,
+    pub config: ProjectConfig,
+}
+
+impl Project {
+    pub fn init(dir: PathBuf, source_bin: Option<PathBuf>, region: String) -> anyhow::Result<Self> {
+        fs::create_dir_all(&dir)?;
+        let dir = dir.canonicalize()?;
+
+        let config_file = dir.join(CONFIG_FILE);
+        if config_file.exists() {
+            anyhow::bail!("{} already exists", config_file.display());
+        }
+
+        let config = ProjectConfig {
+            source_bin: source_bin.map(|bin| bin.canonicalize()).transpose()?,
+            region,
+            ..Default::default()
+        };
+
+        fs::write(config_file, serde_json::to_string_pretty(&config)?)?;
+
+        Ok(Self { dir, config })
+    }
+
+    pub fn open(dir: &Path) -> anyhow::Result<Self> {
+        let dir = dir.canonicalize()?;
+        let config_file = dir.join(CONFIG_FILE);
+
+        let config = serde_json::from_str(&fs::read_to_string(&config_file)?)
+            .with_context(|| config_file.display().to_string())?;
+
+        Ok(Self { dir, config })
+    }
+
+    /// Opens `dir`, or the closest directory holding an s2.json above the
+    /// current one. Without one the current directory is used with the
+    /// default layout.
+    pub fn discover(dir: Option<PathBuf>) -> anyhow::Result<Self> {
+        if let Some(dir) = dir {
+            return Self::open(&dir);
+        }
+
+        let cwd = env::current_dir()?;
+
+        match cwd.ancestors().find(|dir| dir.join(CONFIG_FILE).is_file()) {
+            Some(dir) => Self::open(dir),
+            None => Ok(Self {
+                dir: cwd,
+                config: ProjectConfig::default(),
+            }),
+        }
+    }
+
+    pub fn path(&self, path: &Path) -> PathBuf {
+        self.dir.join(path)
+    }
+
+    pub fn extract_dir(&self) -> PathBuf {
+        self.path(&self.config.extract_dir)
+    }
+
+    pub fn build_dir(&self) -> PathBuf {
+        self.path(&self.config.build_dir)
+    }
+
+    pub fn out_dir(&self) -> PathBuf {
+        self.path(&self.config.out_dir)
+    }
+
+    pub fn iso_xml(&self) -> PathBuf {
+        self.path(&self.config.iso_xml)
+    }
+
+    pub fn patches_dir(&self) -> PathBuf {
+        self.path(&self.config.patches_dir)
+    }
+
+    pub fn cache_file(&self) -> PathBuf {
+        self.dir.join(CACHE_FILE)
+    }
+}
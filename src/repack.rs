@@ -0,0 +1,285 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use anyhow::Context;
+use exe::{EXE_HEADER_LEN, Exe};
+use level::{LevelManifest, sky::import_sky_colors};
+use mips::{
+    overlay::LEVEL_OVERLAY_BASE,
+    patch::{Region, read_patch_file},
+};
+use wad::{Manifest, rebuild_wad};
+
+use crate::{
+    AppliedPatch, LEVELS, Progress,
+    cache::BuildCache,
+    mods::{Conflict, ModManifest, apply_level_mod, apply_mod, find_conflicts, level_mod_inputs},
+    project::Project,
+};
+
+/// What Project::repack built
+pub struct Repacked {
+    pub bin: PathBuf,
+    pub cue: PathBuf,
+    /// Code patches from the patches directory, mods' patches aren't listed
+    pub patches: Vec<AppliedPatch>,
+    /// Edits more than one mod makes, all of them resolved by priority
+    pub conflicts: Vec<Conflict>,
+    /// false when WAD.WAD was reused from the last repack
+    pub wad_rebuilt: bool,
+    /// false when the image was reused from the last repack
+    pub image_rebuilt: bool,
+}
+
+/// Extracted file and address mapping of a patch target
+fn patch_target(root: &Path, exe_name: &str, target: &str) -> anyhow::Result<(PathBuf, Region)> {
+    if target == exe_name {
+        let exe_file = root.join(exe_name);
+        let exe = Exe::load(exe_file.clone())?;
+
+        return Ok((
+            exe_file,
+            Region {
+                base: exe.header.t_addr,
+                header_len: EXE_HEADER_LEN as u64,
+            },
+        ));
+    }
+
+    if !LEVELS.contains(&target) || !target.ends_with(".ovl") {
+        anyhow::bail!("unknown patch target `{target}`");
+    }
+
+    Ok((
+        root.join("WAD").join("levels").join(target),
+        Region {
+            base: LEVEL_OVERLAY_BASE,
+            header_len: 0,
+        },
+    ))
+}
+
+impl Project {
+    /// Applies patches and `mods` and rebuilds WAD.WAD and the image as
+    /// `name`.bin/.cue in the out directory, reusing cached outputs whose
+    /// inputs didn't change unless `clean` is set
+    pub fn repack(
+        &self,
+        name: &str,
+        mods: &[PathBuf],
+        clean: bool,
+        progress: &(dyn Fn(Progress) + Sync),
+    ) -> anyhow::Result<Repacked> {
+        let exe_name = self.config.exe.as_str();
+        let extract_dir = self.extract_dir();
+
+        let cache_file = self.cache_file();
+        let mut cache = if clean {
+            BuildCache::default()
+        } else {
+            BuildCache::load(&cache_file)
+        };
+
+        // patches and mods only ever touch the build copy
+        progress(Progress::Step("Sync extract to build"));
+        let root = self.build_dir();
+        if clean && root.exists() {
+            fs::remove_dir_all(&root)?;
+        }
+        cache.sync_dir(&extract_dir, &root)?;
+
+        let mut mod_manifests = mods
+            .iter()
+            .map(|mod_dir| Ok((ModManifest::load(mod_dir)?, mod_dir)))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        mod_manifests.sort_by_key(|(mod_manifest, _)| mod_manifest.priority);
+
+        let conflicts = find_conflicts(
+            &mod_manifests
+                .iter()
+                .map(|(mod_manifest, _)| mod_manifest.clone())
+                .collect::<Vec<_>>(),
+        )?;
+
+        for conflict in conflicts.iter() {
+            progress(Progress::Conflict(conflict.clone()));
+        }
+
+        let ties = conflicts.iter().filter(|c| c.winner.is_none()).count();
+        if ties > 0 {
+            anyhow::bail!(
+                "{ties} conflicts between mods of equal priority, set `priority` in their mod.json to pick which one wins"
+            );
+        }
+
+        progress(Progress::Step("Rebuild level data"));
+        // collision blobs aren't artifacts of their own, a mod's collision
+        // override is an input of the level data it goes into
+        for level in LEVELS.iter().filter(|l| l.ends_with(".dat")) {
+            let extract_file = extract_dir.join("WAD").join("levels").join(level);
+            let level_file = root.join("WAD").join("levels").join(level);
+            let level_name = level.trim_end_matches("_data.dat");
+
+            let mut level_json = level_file.clone();
+            level_json.set_extension("");
+            level_json.push("level.json");
+
+            let level_manifest = LevelManifest::load(&level_json, &self.dir)?;
+
+            let mut level_inputs = vec![extract_file.clone()];
+            level_inputs.extend(level_manifest.sky_colors.clone());
+            for (mod_manifest, mod_dir) in mod_manifests.iter() {
+                level_inputs.extend(level_mod_inputs(mod_manifest, mod_dir, level_name));
+            }
+            let level_inputs = cache.hash_files(&level_inputs)?;
+
+            if cache.is_fresh(&level_file, &level_inputs)? {
+                continue;
+            }
+
+            fs::copy(&extract_file, &level_file)?;
+            // a sky that didn't decode is passed through untouched
+            if let Some(sky_colors) = &level_manifest.sky_colors {
+                import_sky_colors(level_file.clone(), sky_colors)?;
+            }
+
+            for (mod_manifest, mod_dir) in mod_manifests.iter() {
+                apply_level_mod(mod_manifest, level_name, &level_file)
+                    .with_context(|| format!("{} ({})", mod_manifest.name, mod_dir.display()))?;
+            }
+
+            cache.record(&level_file, level_inputs)?;
+        }
+
+        progress(Progress::Step("Apply code patches"));
+        let mut patches = Vec::new();
+        let patches_dir = self.patches_dir();
+        if patches_dir.is_dir() {
+            let mut patch_files = fs::read_dir(patches_dir)?
+                .map(|entry| Ok(entry?.path()))
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            patch_files.retain(|p| p.extension().is_some_and(|e| e == "json"));
+            patch_files.sort();
+
+            for patch_file in patch_files {
+                let patch_set = read_patch_file(&patch_file)?;
+
+                for patch in patch_set.patches.iter() {
+                    let (target_file, region) = patch_target(&root, exe_name, &patch.target)?;
+
+                    let words = patch.apply(target_file, region).with_context(|| {
+                        format!("{} ({})", patch_set.name, patch_file.display())
+                    })?;
+
+                    let applied = AppliedPatch {
+                        set: patch_set.name.clone(),
+                        target: patch.target.clone(),
+                        address: patch.address.clone(),
+                        words,
+                    };
+
+                    progress(Progress::Patch(applied.clone()));
+                    patches.push(applied);
+                }
+            }
+        }
+
+        for (mod_manifest, mod_dir) in mod_manifests.iter() {
+            progress(Progress::Mod(mod_manifest.name.clone()));
+            apply_mod(mod_manifest, &root, |target| {
+                patch_target(&root, exe_name, target)
+            })
+            .with_context(|| format!("{} ({})", mod_manifest.name, mod_dir.display()))?;
+        }
+
+        progress(Progress::Step("Rebuild WAD.WAD"));
+        // paths resolve against the copy the manifest is loaded from
+        let manifest = Manifest::load(&root.join("WAD.WAD.json"), &self.dir)?;
+
+        let wad_wad = root.join("WAD.WAD");
+        let wad_inputs = cache.hash_files(&manifest.files)?;
+
+        let wad_rebuilt = !cache.is_fresh(&wad_wad, &wad_inputs)?;
+        if wad_rebuilt {
+            rebuild_wad(manifest, wad_wad.clone())?;
+            cache.record(&wad_wad, wad_inputs)?;
+        } else {
+            progress(Progress::UpToDate(wad_wad));
+        }
+
+        progress(Progress::Step("Rebuild ISO"));
+        // make sure folder exists
+        let out_dir = self.out_dir();
+        fs::create_dir_all(&out_dir)?;
+
+        let out_b = out_dir.join(format!("{}.bin", name));
+        let out_c = out_dir.join(format!("{}.cue", name));
+
+        // same layout, sourced from the build copy
+        let out_xml = self.iso_xml().with_extension("build.xml");
+        fs::write(
+            &out_xml,
+            fs::read_to_string(self.iso_xml())?.replace(
+                &format!("\"{}/", self.config.extract_dir.display()),
+                &format!("\"{}/", self.config.build_dir.display()),
+            ),
+        )?;
+
+        // everything the layout pulls in, plus the layout itself
+        let layout = fs::read_to_string(&out_xml)?;
+        let mut image_files = ["source=\"", "file=\""]
+            .iter()
+            .flat_map(|attribute| layout.split(attribute).skip(1))
+            .filter_map(|rest| rest.split('"').next())
+            .map(|source| self.dir.join(source))
+            .filter(|source| source.is_file())
+            .collect::<Vec<_>>();
+        image_files.push(out_xml.clone());
+
+        let image_inputs = cache.hash_files(&image_files)?;
+
+        let image_rebuilt = !(cache.is_fresh(&out_b, &image_inputs)? && out_c.is_file());
+        if image_rebuilt {
+            let output = Command::new("mkpsxiso")
+                .current_dir(&self.dir)
+                .arg("-o")
+                .arg(&out_b)
+                .arg("-c")
+                .arg(&out_c)
+                .arg(out_xml)
+                .output()?;
+
+            if !output.status.success() {
+                // an image left over from an earlier repack isn't this build
+                if out_b.is_file() {
+                    fs::remove_file(&out_b)?;
+                }
+                cache.save(&cache_file)?;
+
+                anyhow::bail!(
+                    "mkpsxiso failed ({}): {}",
+                    output.status,
+                    String::from_utf8_lossy(&output.stderr).trim()
+                );
+            }
+
+            cache.record(&out_b, image_inputs)?;
+        } else {
+            progress(Progress::UpToDate(out_b.clone()));
+        }
+
+        cache.save(&cache_file)?;
+
+        Ok(Repacked {
+            bin: out_b,
+            cue: out_c,
+            patches,
+            conflicts,
+            wad_rebuilt,
+            image_rebuilt,
+        })
+    }
+}
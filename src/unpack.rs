@@ -0,0 +1,204 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use anyhow::Context;
+use collision::parse_collision;
+use level::{gltf::export_level, moby::export_moby, parse_level, sky::export_sky};
+use mips::overlay::{LEVEL_OVERLAY_BASE, Overlay};
+use rayon::{ThreadPoolBuilder, prelude::*};
+use wad::{Manifest, Provenance, parse_wad};
+
+use crate::{LEVELS, Progress, project::Project};
+
+/// What Project::unpack extracted
+pub struct Unpacked {
+    /// WAD.WAD's manifest, level files point into WAD/levels
+    pub wad: Manifest,
+    /// level.json of every level data file
+    pub levels: Vec<PathBuf>,
+}
+
+/// Writes the disassembly of an overlay, or extracts and converts a level
+/// and returns its level.json
+fn unpack_level_file(
+    project_dir: &Path,
+    level_file: &Path,
+    name: &str,
+    provenance: &Provenance,
+    progress: &(dyn Fn(Progress) + Sync),
+) -> anyhow::Result<Option<PathBuf>> {
+    if name.ends_with(".ovl") {
+        let overlay = Overlay::load(level_file.to_path_buf(), LEVEL_OVERLAY_BASE)?;
+
+        let mut listing = level_file.to_path_buf();
+        listing.set_extension("s");
+
+        fs::write(listing, overlay.listing())?;
+    }
+
+    if name.ends_with(".dat") {
+        let mut output_dir = level_file.to_path_buf();
+        output_dir.pop();
+        output_dir.push(name.trim_end_matches(".dat"));
+
+        let level_manifest = parse_level(
+            level_file.to_path_buf(),
+            output_dir.clone(),
+            provenance.clone(),
+        )?;
+
+        let mut level_json = output_dir.clone();
+        level_json.push("level.json");
+
+        level_manifest.save(&level_json, project_dir)?;
+
+        let mut model_glb = output_dir.clone();
+        model_glb.push("model.glb");
+
+        // previews are best effort, the extracted data is what matters
+        if let Err(e) = export_level(&level_manifest, model_glb) {
+            progress(Progress::Warning(format!(
+                "{name}: skipped model.glb: {e:#}"
+            )));
+        }
+
+        let mut sky_glb = output_dir.clone();
+        sky_glb.push("sky.glb");
+
+        // sky polygons are only partly understood, many don't export
+        if let Err(e) = export_sky(&level_manifest, sky_glb) {
+            progress(Progress::Warning(format!("{name}: skipped sky.glb: {e:#}")));
+        }
+
+        for moby_model in level_manifest.moby_models.iter() {
+            let mut moby_glb = moby_model.file.clone();
+            moby_glb.set_extension("glb");
+
+            // a broken model only costs its preview, not the level
+            if let Err(e) = export_moby(moby_model.file.clone(), moby_glb) {
+                progress(Progress::Warning(format!(
+                    "{name}: skipped {}: {e:#}",
+                    moby_model.file.display()
+                )));
+            }
+        }
+
+        output_dir.push("colission");
+
+        let collision_manifest =
+            parse_collision(level_manifest.collision_data, output_dir.clone())?;
+
+        collision_manifest.save(&output_dir.join("collision.json"))?;
+
+        return Ok(Some(level_json));
+    }
+
+    Ok(None)
+}
+
+impl Project {
+    /// Dumps `target_bin`, or the project's source_bin, into the extract
+    /// directory and converts WAD.WAD and the levels. `jobs` levels are
+    /// extracted at once, one per CPU by default.
+    pub fn unpack(
+        &self,
+        target_bin: Option<&Path>,
+        jobs: Option<usize>,
+        progress: &(dyn Fn(Progress) + Sync),
+    ) -> anyhow::Result<Unpacked> {
+        let target_bin = match target_bin {
+            Some(target_bin) => target_bin.canonicalize()?,
+            None => self.config.source_bin.clone().ok_or_else(|| {
+                anyhow::anyhow!("no bin file given and the project has no source_bin")
+            })?,
+        };
+
+        progress(Progress::Step("Unpacking ISO"));
+        // dumpsxiso records paths relative to where it runs
+        let output = Command::new("dumpsxiso")
+            .current_dir(&self.dir)
+            .arg("-x")
+            .arg(&self.config.extract_dir)
+            .arg("-s")
+            .arg(&self.config.iso_xml)
+            .arg(&target_bin)
+            .output()?;
+
+        if !output.status.success() {
+            anyhow::bail!(
+                "dumpsxiso failed ({}): {}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+
+        let extract_dir = self.extract_dir();
+
+        progress(Progress::Step("Unpacking main WAD file"));
+        let wad_file = extract_dir.join("WAD.WAD");
+        let output_dir = extract_dir.join("WAD");
+        // every manifest records the image, not the file it was cut from
+        let provenance = Provenance::of(&target_bin, concat!("s2 ", env!("CARGO_PKG_VERSION")))?;
+        let mut manifest = parse_wad(wad_file, output_dir, provenance.clone())?;
+
+        progress(Progress::Step("Moving files"));
+        fs::create_dir_all(extract_dir.join("WAD").join("levels"))?;
+
+        for (i, wfile) in manifest.files.iter_mut().skip(14).take(29 * 2).enumerate() {
+            let mut new_name = wfile.clone();
+            new_name.pop();
+            new_name.push("levels");
+            new_name.push(LEVELS[i]);
+
+            fs::rename(&wfile, &new_name)?;
+
+            *wfile = new_name;
+        }
+
+        progress(Progress::Step("Handling levels"));
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(jobs.unwrap_or(0))
+            .build()?;
+
+        let level_files = manifest
+            .files
+            .iter()
+            .skip(14)
+            .zip(LEVELS)
+            .collect::<Vec<_>>();
+
+        let done = AtomicUsize::new(0);
+
+        // levels only write below their own directory
+        let levels = pool.install(|| {
+            level_files
+                .par_iter()
+                .map(|(level_file, name)| {
+                    let level_json =
+                        unpack_level_file(&self.dir, level_file, name, &provenance, progress)
+                            .with_context(|| name.to_string())?;
+
+                    progress(Progress::Level {
+                        name,
+                        done: done.fetch_add(1, Ordering::Relaxed) + 1,
+                        total: level_files.len(),
+                    });
+
+                    Ok(level_json)
+                })
+                .collect::<anyhow::Result<Vec<_>>>()
+        })?;
+
+        progress(Progress::Step("Save WAD.WAD.json"));
+        manifest.save(&extract_dir.join("WAD.WAD.json"), &self.dir)?;
+
+        Ok(Unpacked {
+            wad: manifest,
+            levels: levels.into_iter().flatten().collect(),
+        })
+    }
+}
@@ -0,0 +1,88 @@
+use std::{collections::BTreeSet, path::PathBuf, sync::mpsc, time::Duration};
+
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+
+use crate::{Progress, cache::BuildCache, project::Project};
+
+/// Saving one of these starts a rebuild, as does any other file the last
+/// repack read
+const WATCHED_EXTENSIONS: [&str; 3] = ["json", "tim", "vag"];
+
+/// Editors save in several steps, wait for the burst of events to end
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+fn run(rebuild: &mut impl FnMut() -> anyhow::Result<()>, progress: &dyn Fn(Progress)) {
+    if let Err(e) = rebuild() {
+        progress(Progress::RebuildFailed(format!("{e:#}")));
+    }
+}
+
+/// Runs `rebuild` once, then again whenever a watched file in the project
+/// or one of the mod directories changes. Only returns on watcher errors.
+pub fn watch(
+    project: &Project,
+    mod_dirs: &[PathBuf],
+    mut rebuild: impl FnMut() -> anyhow::Result<()>,
+    progress: &dyn Fn(Progress),
+) -> anyhow::Result<()> {
+    let (tx, rx) = mpsc::channel();
+
+    let mut watcher = notify::recommended_watcher(tx)?;
+    watcher.watch(&project.dir, RecursiveMode::Recursive)?;
+
+    for mod_dir in mod_dirs {
+        let mod_dir = mod_dir.canonicalize()?;
+
+        if !mod_dir.starts_with(&project.dir) {
+            watcher.watch(&mod_dir, RecursiveMode::Recursive)?;
+        }
+    }
+
+    // written by the rebuild itself
+    let ignored = [
+        project.build_dir(),
+        project.out_dir(),
+        project.cache_file(),
+        project.iso_xml().with_extension("build.xml"),
+    ];
+
+    // mod files and extracted sources the cache hashed
+    let recorded_inputs = || -> BTreeSet<PathBuf> {
+        BuildCache::load(&project.cache_file())
+            .files()
+            .filter_map(|path| path.canonicalize().ok())
+            .collect()
+    };
+
+    let is_relevant = |event: &Event, inputs: &BTreeSet<PathBuf>| {
+        matches!(
+            event.kind,
+            EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+        ) && event.paths.iter().any(|path| {
+            !ignored.iter().any(|ignored| path.starts_with(ignored))
+                && (inputs.contains(path)
+                    || path.extension().and_then(|e| e.to_str()).is_some_and(|e| {
+                        WATCHED_EXTENSIONS.contains(&e.to_ascii_lowercase().as_str())
+                    }))
+        })
+    };
+
+    run(&mut rebuild, progress);
+    let mut inputs = recorded_inputs();
+    progress(Progress::Watching(project.dir.clone()));
+
+    loop {
+        let event = rx.recv()??;
+
+        if !is_relevant(&event, &inputs) {
+            continue;
+        }
+
+        while rx.recv_timeout(DEBOUNCE).is_ok() {}
+
+        progress(Progress::Changed(event.paths[0].clone()));
+        run(&mut rebuild, progress);
+        inputs = recorded_inputs();
+        progress(Progress::Watching(project.dir.clone()));
+    }
+}
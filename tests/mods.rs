@@ -0,0 +1,117 @@
+use s2::mods::{ModManifest, find_conflicts};
+use serde_json::{Value, json};
+
+fn manifest(name: &str, edits: Value) -> ModManifest {
+    let mut manifest = json!({ "name": name });
+    manifest
+        .as_object_mut()
+        .unwrap()
+        .extend(edits.as_object().unwrap().clone());
+
+    serde_json::from_value(manifest).unwrap()
+}
+
+fn texture(x: u16, y: u16, width: u16, height: u16) -> Value {
+    json!({
+        "textures": [{
+            "level": "level_10_summer_forest",
+            "page": 0,
+            "file": "tex_0.tim",
+            "rect": { "x": x, "y": y, "width": width, "height": height },
+        }]
+    })
+}
+
+fn triangles(start: u32, end: u32) -> Value {
+    json!({
+        "collision": [{
+            "level": "level_10_summer_forest",
+            "file": "collision_data.bin",
+            "triangles": { "start": start, "end": end },
+        }]
+    })
+}
+
+fn conflicts(first: Value, second: Value) -> Vec<String> {
+    find_conflicts(&[manifest("first", first), manifest("second", second)])
+        .unwrap()
+        .iter()
+        .map(|conflict| conflict.edit.to_string())
+        .collect()
+}
+
+#[test]
+fn overlapping_texture_rects_conflict() {
+    assert_eq!(
+        conflicts(texture(0, 0, 64, 64), texture(32, 32, 64, 64)),
+        ["level_10_summer_forest_data.dat texture page 0 64x64 at 0,0"]
+    );
+}
+
+#[test]
+fn disjoint_texture_rects_dont_conflict() {
+    assert!(conflicts(texture(0, 0, 64, 64), texture(64, 0, 64, 64)).is_empty());
+    assert!(conflicts(texture(0, 0, 64, 64), texture(0, 64, 64, 64)).is_empty());
+
+    let mut other_page = texture(0, 0, 64, 64);
+    other_page["textures"][0]["page"] = json!(1);
+    assert!(conflicts(texture(0, 0, 64, 64), other_page).is_empty());
+}
+
+#[test]
+fn a_whole_page_conflicts_with_any_rect_on_it() {
+    let mut whole_page = texture(0, 0, 0, 0);
+    whole_page["textures"][0]
+        .as_object_mut()
+        .unwrap()
+        .remove("rect");
+
+    assert_eq!(conflicts(whole_page, texture(500, 250, 12, 6)).len(), 1);
+}
+
+#[test]
+fn overlapping_triangles_conflict() {
+    assert_eq!(
+        conflicts(triangles(0, 10), triangles(9, 20)),
+        ["level_10_summer_forest_data.dat collision triangles 0..10"]
+    );
+}
+
+#[test]
+fn disjoint_triangles_dont_conflict() {
+    assert!(conflicts(triangles(0, 10), triangles(10, 20)).is_empty());
+}
+
+#[test]
+fn replacing_the_collision_conflicts_with_any_triangles() {
+    let mut whole = triangles(0, 0);
+    whole["collision"][0]
+        .as_object_mut()
+        .unwrap()
+        .remove("triangles");
+
+    assert_eq!(
+        conflicts(whole, triangles(3, 4)),
+        ["level_10_summer_forest_data.dat collision"]
+    );
+}
+
+#[test]
+fn replacing_the_level_conflicts_with_every_edit_in_it() {
+    let level = json!({ "files": { "level_10_summer_forest_data.dat": "level.dat" } });
+
+    assert_eq!(
+        conflicts(level, texture(0, 0, 8, 8)),
+        ["level_10_summer_forest_data.dat texture page 0 8x8 at 0,0"]
+    );
+}
+
+#[test]
+fn priorities_pick_a_winner() {
+    let mut second = manifest("second", texture(0, 0, 8, 8));
+    second.priority = 1;
+
+    let conflicts = find_conflicts(&[manifest("first", texture(0, 0, 8, 8)), second]).unwrap();
+
+    assert_eq!(conflicts[0].winner.as_deref(), Some("second"));
+}
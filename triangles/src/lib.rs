@@ -4,7 +4,7 @@ use std::{
     path::PathBuf,
 };
 
-pub struct Vec3<T>(T, T, T);
+pub struct Vec3<T>(pub T, pub T, pub T);
 
 pub struct Triangle {
     pub v1: Vec3<i32>,
@@ -12,7 +12,7 @@ pub struct Triangle {
     pub v3: Vec3<i32>,
 }
 
-pub fn read_tri(file: &mut File) -> anyhow::Result<Triangle> {
+pub fn read_tri(file: &mut impl Read) -> anyhow::Result<Triangle> {
     let mut buffer_0 = [0u8; 4];
     let mut buffer_1 = [0u8; 4];
     let mut buffer_2 = [0u8; 4];
@@ -53,6 +53,64 @@ pub fn read_tri(file: &mut File) -> anyhow::Result<Triangle> {
     })
 }
 
+/// Packs one axis as the first vertex in 14 bits and the deltas to the
+/// other two in `delta_bits` each, signed for x and y, unsigned for z
+fn pack_axis(
+    axis: char,
+    values: [i32; 3],
+    delta_bits: u32,
+    signed: bool,
+) -> anyhow::Result<[u32; 3]> {
+    if let Some(value) = values.iter().find(|value| *value & 0xf != 0) {
+        anyhow::bail!("{axis} coordinate {value} isn't a multiple of 16");
+    }
+
+    let base = values[0] >> 4;
+    if !(0..0x4000).contains(&base) {
+        anyhow::bail!("{axis} coordinate {} is out of range", values[0]);
+    }
+
+    let deltas = if signed {
+        -(1 << (delta_bits - 1))..1 << (delta_bits - 1)
+    } else {
+        0..1 << delta_bits
+    };
+    let mask = (1 << delta_bits) - 1;
+
+    let mut packed = [base as u32, 0, 0];
+    for i in 1..3 {
+        let delta = (values[i] >> 4) as i64 - base as i64;
+
+        if !deltas.contains(&delta) {
+            anyhow::bail!(
+                "{axis} coordinates {} and {} are too far apart to pack",
+                values[0],
+                values[i]
+            );
+        }
+
+        packed[i] = delta as u32 & mask;
+    }
+
+    Ok(packed)
+}
+
+/// Packs a triangle back into its 12 bytes. Each axis stores the first
+/// vertex and two deltas, bits 14 and 15 of the packed z are never read
+/// and are written as zeros. Coordinates the format can't hold are an
+/// error.
+pub fn write_tri(triangle: &Triangle, file: &mut impl Write) -> anyhow::Result<()> {
+    let [x, x2, x3] = pack_axis('x', [triangle.v1.0, triangle.v2.0, triangle.v3.0], 9, true)?;
+    let [y, y2, y3] = pack_axis('y', [triangle.v1.1, triangle.v2.1, triangle.v3.1], 9, true)?;
+    let [z, z2, z3] = pack_axis('z', [triangle.v1.2, triangle.v2.2, triangle.v3.2], 8, false)?;
+
+    file.write_all(&(x | x2 << 14 | x3 << 23).to_le_bytes())?;
+    file.write_all(&(y | y2 << 14 | y3 << 23).to_le_bytes())?;
+    file.write_all(&(z | z2 << 16 | z3 << 24).to_le_bytes())?;
+
+    Ok(())
+}
+
 pub fn convert(file_pb: PathBuf) -> anyhow::Result<()> {
     let mut file = File::open(&file_pb)?;
 
@@ -68,7 +126,7 @@ pub fn convert(file_pb: PathBuf) -> anyhow::Result<()> {
             Err(_) => break,
         };
 
-        dst_file.write(
+        dst_file.write_all(
             format!(
                 "v {} {} {}\n",
                 triangle.v1.0 as f32 / 4096.0,
@@ -77,7 +135,7 @@ pub fn convert(file_pb: PathBuf) -> anyhow::Result<()> {
             )
             .as_bytes(),
         )?;
-        dst_file.write(
+        dst_file.write_all(
             format!(
                 "v {} {} {}\n",
                 triangle.v2.0 as f32 / 4096.0,
@@ -86,7 +144,7 @@ pub fn convert(file_pb: PathBuf) -> anyhow::Result<()> {
             )
             .as_bytes(),
         )?;
-        dst_file.write(
+        dst_file.write_all(
             format!(
                 "v {} {} {}\n",
                 triangle.v3.0 as f32 / 4096.0,
@@ -100,7 +158,7 @@ pub fn convert(file_pb: PathBuf) -> anyhow::Result<()> {
     }
 
     for i in 0..face_counter {
-        dst_file.write(format!("f {} {} {}\n", i * 3 + 1, i * 3 + 2, i * 3 + 3).as_bytes())?;
+        dst_file.write_all(format!("f {} {} {}\n", i * 3 + 1, i * 3 + 2, i * 3 + 3).as_bytes())?;
     }
 
     Ok(())
@@ -0,0 +1,69 @@
+use std::io::Cursor;
+
+use proptest::prelude::*;
+use triangles::{Triangle, Vec3, read_tri, write_tri};
+
+proptest! {
+    #[test]
+    fn read_then_write_is_identical(bytes in fixtures::triangles::packed_triangles()) {
+        let mut reader = Cursor::new(&bytes);
+        let mut written = Vec::new();
+
+        for _ in 0..bytes.len() / 12 {
+            write_tri(&read_tri(&mut reader).unwrap(), &mut written).unwrap();
+        }
+
+        prop_assert_eq!(written, bytes);
+    }
+}
+
+fn triangle(v1: [i32; 3], v2: [i32; 3], v3: [i32; 3]) -> Triangle {
+    Triangle {
+        v1: Vec3(v1[0], v1[1], v1[2]),
+        v2: Vec3(v2[0], v2[1], v2[2]),
+        v3: Vec3(v3[0], v3[1], v3[2]),
+    }
+}
+
+#[test]
+fn deltas_at_the_limits_are_written() {
+    let limits = triangle(
+        [0x200 * 16, 0x200 * 16, 0],
+        [(0x200 - 256) * 16, (0x200 + 255) * 16, 255 * 16],
+        [(0x200 + 255) * 16, (0x200 - 256) * 16, 0],
+    );
+
+    let mut written = Vec::new();
+    write_tri(&limits, &mut written).unwrap();
+    let read = read_tri(&mut Cursor::new(&written)).unwrap();
+
+    assert_eq!(
+        (read.v2.0, read.v2.1, read.v2.2),
+        (limits.v2.0, limits.v2.1, limits.v2.2)
+    );
+    assert_eq!(
+        (read.v3.0, read.v3.1, read.v3.2),
+        (limits.v3.0, limits.v3.1, limits.v3.2)
+    );
+}
+
+#[test]
+fn unrepresentable_triangles_are_an_error() {
+    let cases = [
+        // deltas one past the limit on each axis
+        triangle([4096, 0, 0], [4096 + 256 * 16, 0, 0], [4096, 0, 0]),
+        triangle([0, 4096, 0], [0, 4096, 0], [0, 4096 - 257 * 16, 0]),
+        triangle([0, 0, 4096], [0, 0, 4096 - 16], [0, 0, 4096]),
+        triangle([0, 0, 0], [0, 0, 256 * 16], [0, 0, 0]),
+        // far apart enough to overflow a plain subtraction
+        triangle([0, 0, 0], [i32::MIN, 0, 0], [i32::MAX & !0xf, 0, 0]),
+        // first vertex outside 14 bits, or between steps of 16
+        triangle([0x4000 * 16, 0, 0], [0, 0, 0], [0, 0, 0]),
+        triangle([-16, 0, 0], [0, 0, 0], [0, 0, 0]),
+        triangle([0, 8, 0], [0, 8, 0], [0, 8, 0]),
+    ];
+
+    for case in cases {
+        assert!(write_tri(&case, &mut Vec::new()).is_err());
+    }
+}
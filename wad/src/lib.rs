@@ -1,11 +1,60 @@
 use std::{
     fs::{File, create_dir_all, metadata},
-    io::{Read, Seek, Write as _, copy},
-    path::PathBuf,
+    io::{self, Read, Seek, Write, copy},
+    path::{Component, Path, PathBuf},
 };
 
-use chrono::{DateTime, Local};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum WadError {
+    #[error("{}", path.display())]
+    Io { path: PathBuf, source: io::Error },
+    #[error("{}: {section} at offset 0x{offset:x}", path.display())]
+    Read {
+        path: PathBuf,
+        section: String,
+        offset: u64,
+        source: io::Error,
+    },
+    #[error("{}: header entry {entry}: {reason}", path.display())]
+    Invalid {
+        path: PathBuf,
+        entry: usize,
+        reason: String,
+    },
+    #[error("the manifest lists no files")]
+    Empty,
+    #[error("{} doesn't fit, a WAD ends before 4 GiB", path.display())]
+    TooLarge { path: PathBuf },
+}
+
+impl WadError {
+    pub fn io(path: &Path) -> impl FnOnce(io::Error) -> Self {
+        let path = path.to_path_buf();
+
+        move |source| Self::Io { path, source }
+    }
+
+    pub fn read(
+        path: &Path,
+        section: impl Into<String>,
+        offset: u64,
+    ) -> impl FnOnce(io::Error) -> Self {
+        let path = path.to_path_buf();
+        let section = section.into();
+
+        move |source| Self::Read {
+            path,
+            section,
+            offset,
+            source,
+        }
+    }
+}
 
 #[derive(Copy, Clone, Debug)]
 pub struct WADFile {
@@ -24,14 +73,229 @@ pub struct WADHeader {
     pub files: [WADFile; 256],
 }
 
-#[derive(Serialize, Deserialize)]
+pub const SCHEMA_VERSION: u32 = 2;
+
+const MIGRATIONS: [Migration; SCHEMA_VERSION as usize] = [
+    // paths relative to the manifest
+    |manifest, base| {
+        if let Some(Value::Array(files)) = manifest.get_mut("files") {
+            files
+                .iter_mut()
+                .for_each(|file| rebase_legacy_path(file, base));
+        }
+
+        Ok(())
+    },
+    replace_timestamp,
+];
+
+/// What a manifest was extracted from, in place of a timestamp so
+/// unpacking the same image twice gives identical files
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct Provenance {
+    /// SHA-256 of the disc image the files came from, empty for upgraded
+    /// manifests
+    pub source_sha256: String,
+    /// Crate and version that wrote the manifest
+    pub tool_version: String,
+}
+
+impl Provenance {
+    pub fn of(source_file: &Path, tool_version: &str) -> io::Result<Self> {
+        let mut hasher = Sha256::new();
+        copy(&mut File::open(source_file)?, &mut hasher)?;
+
+        Ok(Self {
+            source_sha256: format!("{:x}", hasher.finalize()),
+            tool_version: tool_version.into(),
+        })
+    }
+}
+
+/// Migration dropping the version 1 timestamp for an unknown provenance
+pub fn replace_timestamp(manifest: &mut Map<String, Value>, _: &Path) -> anyhow::Result<()> {
+    manifest.remove("timestamp");
+    manifest.insert(
+        "provenance".into(),
+        serde_json::to_value(Provenance::default())?,
+    );
+
+    Ok(())
+}
+
+/// Manifest files store paths relative to their own directory
+#[derive(Serialize, Deserialize, Clone)]
 pub struct Manifest {
-    pub timestamp: DateTime<Local>,
+    pub schema_version: u32,
+    pub provenance: Provenance,
     pub files: Vec<PathBuf>,
 }
 
-pub fn parse_wad(wad_file: PathBuf, output_dir: PathBuf) -> anyhow::Result<Manifest> {
-    let mut file = File::open(&wad_file)?;
+/// Folds `.` and `..` out of a path without touching the file system,
+/// None if `..` climbs above its start
+fn normalize(path: &Path) -> Option<PathBuf> {
+    let mut normalized = PathBuf::new();
+
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    return None;
+                }
+            }
+            component => normalized.push(component),
+        }
+    }
+
+    Some(normalized)
+}
+
+/// Resolves a manifest path against `base`, the manifest's directory.
+/// Absolute paths and ones that end up outside `root`, the project
+/// directory, are rejected.
+pub fn resolve_path(root: &Path, base: &Path, path: &Path) -> anyhow::Result<PathBuf> {
+    let escapes = || {
+        anyhow::anyhow!(
+            "manifest path {} escapes {}",
+            path.display(),
+            root.display()
+        )
+    };
+
+    if path.has_root() || path.components().any(|c| matches!(c, Component::Prefix(_))) {
+        return Err(escapes());
+    }
+
+    let resolved = normalize(&base.join(path)).ok_or_else(escapes)?;
+    let root = normalize(root).ok_or_else(escapes)?;
+
+    if !resolved.starts_with(&root) {
+        return Err(escapes());
+    }
+
+    Ok(resolved)
+}
+
+/// Inverse of resolve_path, `path` must be inside `root`
+pub fn relative_path(root: &Path, base: &Path, path: &Path) -> anyhow::Result<PathBuf> {
+    let outside = || anyhow::anyhow!("{} is outside of {}", path.display(), root.display());
+
+    let path = normalize(path).ok_or_else(outside)?;
+    let base = normalize(base).ok_or_else(outside)?;
+
+    if !path.starts_with(normalize(root).ok_or_else(outside)?) {
+        return Err(outside());
+    }
+
+    let common = path
+        .components()
+        .zip(base.components())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let mut relative = PathBuf::new();
+    base.components()
+        .skip(common)
+        .for_each(|_| relative.push(".."));
+    path.components()
+        .skip(common)
+        .for_each(|component| relative.push(component));
+
+    Ok(relative)
+}
+
+/// Directory manifest paths are relative to
+pub fn manifest_dir(json_file: &Path) -> &Path {
+    json_file.parent().unwrap_or(Path::new(""))
+}
+
+/// Upgrades a manifest object by one schema version, gets the manifest's
+/// directory
+pub type Migration = fn(&mut Map<String, Value>, &Path) -> anyhow::Result<()>;
+
+/// Reads a manifest, running `migrations[v..]` on files at schema version v
+pub fn read_manifest<T: DeserializeOwned>(
+    json_file: &Path,
+    migrations: &[Migration],
+) -> anyhow::Result<T> {
+    let Value::Object(mut manifest) = serde_json::from_reader(File::open(json_file)?)? else {
+        anyhow::bail!("{} is not a manifest", json_file.display());
+    };
+
+    let supported = migrations.len() as u64;
+
+    // manifests from before versioning have no schema_version
+    let version = match manifest.get("schema_version") {
+        None => 0,
+        Some(version) => version.as_u64().ok_or_else(|| {
+            anyhow::anyhow!("{}: schema_version must be a number", json_file.display())
+        })?,
+    };
+
+    if version > supported {
+        anyhow::bail!(
+            "{} has schema version {version} but this s2 only reads up to {supported}, update s2",
+            json_file.display()
+        );
+    }
+
+    for migration in migrations[version as usize..].iter() {
+        migration(&mut manifest, manifest_dir(json_file))
+            .map_err(|e| anyhow::anyhow!("{}: {e}", json_file.display()))?;
+    }
+
+    manifest.insert("schema_version".into(), supported.into());
+
+    serde_json::from_value(Value::Object(manifest))
+        .map_err(|e| anyhow::anyhow!("{}: {e}", json_file.display()))
+}
+
+/// Version 0 manifests stored paths relative to wherever Unpack ran, keeps
+/// the part below the manifest's directory
+pub fn rebase_legacy_path(path: &mut Value, base: &Path) {
+    let (Some(dir_name), Some(legacy)) = (base.file_name(), path.as_str()) else {
+        return;
+    };
+
+    let components = Path::new(legacy).components().collect::<Vec<_>>();
+
+    if let Some(i) = components.iter().rposition(|c| c.as_os_str() == dir_name) {
+        let rebased = components[i + 1..].iter().collect::<PathBuf>();
+
+        *path = Value::String(rebased.to_string_lossy().into());
+    }
+}
+
+impl Manifest {
+    /// Loads a manifest whose files must all be inside `root`
+    pub fn load(json_file: &Path, root: &Path) -> anyhow::Result<Self> {
+        let mut manifest: Self = read_manifest(json_file, &MIGRATIONS)?;
+        let base = manifest_dir(json_file);
+
+        for file in manifest.files.iter_mut() {
+            *file = resolve_path(root, base, file)?;
+        }
+
+        Ok(manifest)
+    }
+
+    pub fn save(&self, json_file: &Path, root: &Path) -> anyhow::Result<()> {
+        let mut manifest = self.clone();
+        let base = manifest_dir(json_file);
+
+        for file in manifest.files.iter_mut() {
+            *file = relative_path(root, base, file)?;
+        }
+
+        serde_json::to_writer_pretty(File::create(json_file)?, &manifest)?;
+
+        Ok(())
+    }
+}
+
+/// Reads the file table, `name` is only used in errors
+pub fn read_wad_header(reader: &mut impl Read, name: &Path) -> Result<WADHeader, WadError> {
     let mut header = WADHeader {
         files: [WADFile {
             offset: 0,
@@ -42,8 +306,12 @@ pub fn parse_wad(wad_file: PathBuf, output_dir: PathBuf) -> anyhow::Result<Manif
     let mut offset = [0u8; 4];
     let mut length = [0u8; 4];
     for i in 0..256 {
-        file.read_exact(&mut offset)?;
-        file.read_exact(&mut length)?;
+        let entry_error = WadError::read(name, format!("header entry {i}"), i as u64 * 8);
+
+        reader
+            .read_exact(&mut offset)
+            .and_then(|_| reader.read_exact(&mut length))
+            .map_err(entry_error)?;
 
         header.files[i] = WADFile {
             offset: u32::from_le_bytes(offset),
@@ -51,30 +319,139 @@ pub fn parse_wad(wad_file: PathBuf, output_dir: PathBuf) -> anyhow::Result<Manif
         };
     }
 
-    create_dir_all(&output_dir)?;
+    Ok(header)
+}
+
+/// Size of the file table, files start after it
+pub const WAD_HEADER_LEN: u64 = 256 * 8;
+
+/// Checks every file of the table lies between the table and the end of
+/// the WAD, in order and without overlapping the one before
+pub fn validate_wad_header(header: &WADHeader, file_len: u64, name: &Path) -> Result<(), WadError> {
+    let invalid = |entry: usize, reason: String| WadError::Invalid {
+        path: name.to_path_buf(),
+        entry,
+        reason,
+    };
+
+    let mut previous_end = WAD_HEADER_LEN;
+
+    for (i, wfile) in header
+        .files
+        .iter()
+        .take_while(|wfile| !wfile.end())
+        .enumerate()
+    {
+        let end = wfile.offset.checked_add(wfile.length).ok_or_else(|| {
+            invalid(
+                i,
+                format!(
+                    "0x{:x} bytes at 0x{:x} run past 4 GiB",
+                    wfile.length, wfile.offset
+                ),
+            )
+        })? as u64;
+
+        if (wfile.offset as u64) < previous_end {
+            return Err(invalid(
+                i,
+                format!(
+                    "starts at 0x{:x}, before the end of the previous one at 0x{previous_end:x}",
+                    wfile.offset
+                ),
+            ));
+        }
+
+        if end > file_len {
+            return Err(invalid(
+                i,
+                format!(
+                    "0x{:x} bytes at 0x{:x} run past the end of the file at 0x{file_len:x}",
+                    wfile.length, wfile.offset
+                ),
+            ));
+        }
+
+        previous_end = end;
+    }
+
+    Ok(())
+}
+
+/// Copies entry `i` of the file table to `dst`
+fn copy_wad_entry(
+    reader: &mut (impl Read + Seek),
+    name: &Path,
+    i: usize,
+    wfile: &WADFile,
+    dst: &mut impl Write,
+) -> Result<(), WadError> {
+    reader
+        .seek(std::io::SeekFrom::Start(wfile.offset as u64))
+        .and_then(|_| copy_exact(reader, dst, wfile.length as u64))
+        .map_err(WadError::read(
+            name,
+            format!("file {i}"),
+            wfile.offset as u64,
+        ))
+}
+
+/// Reads every file of a WAD into memory, in table order
+pub fn read_wad(reader: &mut (impl Read + Seek), name: &Path) -> Result<Vec<Vec<u8>>, WadError> {
+    let header = read_wad_header(reader, name)?;
+
+    let file_len = reader
+        .seek(std::io::SeekFrom::End(0))
+        .map_err(WadError::io(name))?;
+    validate_wad_header(&header, file_len, name)?;
+
+    header
+        .files
+        .iter()
+        .take_while(|wfile| !wfile.end())
+        .enumerate()
+        .map(|(i, wfile)| {
+            let mut data = Vec::new();
+            copy_wad_entry(reader, name, i, wfile, &mut data)?;
+
+            Ok(data)
+        })
+        .collect()
+}
+
+/// Extracts every file of a WAD into `output_dir`. Entries are streamed to
+/// disk one at a time instead of going through read_wad, so the whole WAD
+/// is never held in memory.
+pub fn parse_wad(
+    wad_file: PathBuf,
+    output_dir: PathBuf,
+    provenance: Provenance,
+) -> Result<Manifest, WadError> {
+    let mut file = File::open(&wad_file).map_err(WadError::io(&wad_file))?;
+    let header = read_wad_header(&mut file, &wad_file)?;
+
+    let file_len = file.metadata().map_err(WadError::io(&wad_file))?.len();
+    validate_wad_header(&header, file_len, &wad_file)?;
+
+    create_dir_all(&output_dir).map_err(WadError::io(&output_dir))?;
 
     let mut manifest = Manifest {
-        timestamp: Local::now(),
+        schema_version: SCHEMA_VERSION,
+        provenance,
         files: Vec::new(),
     };
 
-    let mut file = File::open(&wad_file)?;
-
     for (i, wfile) in header.files.iter().enumerate() {
         if wfile.end() {
             break;
         }
 
-        file.seek(std::io::SeekFrom::Start(wfile.offset as u64))?;
-
         let mut dst = output_dir.clone();
         dst.push(format!("{}.bin", i));
 
-        let mut dst_file = File::create(&dst)?;
-
-        let mut limited = Read::by_ref(&mut file).take(wfile.length as u64);
+        let mut dst_file = File::create(&dst).map_err(WadError::io(&dst))?;
 
-        copy(&mut limited, &mut dst_file)?;
+        copy_wad_entry(&mut file, &wad_file, i, wfile, &mut dst_file)?;
 
         manifest.files.push(dst);
     }
@@ -82,7 +459,30 @@ pub fn parse_wad(wad_file: PathBuf, output_dir: PathBuf) -> anyhow::Result<Manif
     Ok(manifest)
 }
 
-pub fn rebuild_wad(manifest: Manifest, output_file: PathBuf) -> anyhow::Result<()> {
+/// Copies exactly `length` bytes, a short source is an UnexpectedEof error
+pub fn copy_exact(src: &mut impl Read, dst: &mut impl Write, length: u64) -> io::Result<()> {
+    let copied = copy(&mut src.take(length), dst)?;
+
+    if copied < length {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            format!("needed 0x{length:x} bytes but the file ends after 0x{copied:x}"),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Reads exactly `length` bytes, the buffer grows with what is read so a
+/// bogus length can't allocate more than the source holds
+pub fn read_exact_vec(src: &mut impl Read, length: u64) -> io::Result<Vec<u8>> {
+    let mut buffer = Vec::new();
+    copy_exact(src, &mut buffer, length)?;
+
+    Ok(buffer)
+}
+
+pub fn rebuild_wad(manifest: Manifest, output_file: PathBuf) -> Result<(), WadError> {
     let mut header = WADHeader {
         files: [WADFile {
             offset: 0,
@@ -90,37 +490,46 @@ pub fn rebuild_wad(manifest: Manifest, output_file: PathBuf) -> anyhow::Result<(
         }; 256],
     };
 
-    let meta = metadata(&manifest.files[0])?;
+    let Some(first) = manifest.files.first() else {
+        return Err(WadError::Empty);
+    };
 
-    header.files[0].length = meta.len() as u32;
-    header.files[0].offset = 2048;
+    let too_large = |path: &Path| WadError::TooLarge {
+        path: path.to_path_buf(),
+    };
+    let length = |path: &Path| {
+        let meta = metadata(path).map_err(WadError::io(path))?;
+
+        u32::try_from(meta.len()).map_err(|_| too_large(path))
+    };
+
+    header.files[0].length = length(first)?;
+    header.files[0].offset = WAD_HEADER_LEN as u32;
 
     for i in 1..manifest.files.len().min(256) {
-        let meta = metadata(&manifest.files[i])?;
+        let path = &manifest.files[i];
 
-        header.files[i].length = meta.len() as u32;
-        header.files[i].offset = header.files[i - 1].offset + header.files[i - 1].length;
+        header.files[i].length = length(path)?;
+        header.files[i].offset = header.files[i - 1]
+            .offset
+            .checked_add(header.files[i - 1].length)
+            .filter(|offset| offset.checked_add(header.files[i].length).is_some())
+            .ok_or_else(|| too_large(path))?;
     }
 
-    let mut wad_file = File::create(output_file)?;
+    let mut wad_file = File::create(&output_file).map_err(WadError::io(&output_file))?;
 
     for wfile in header.files {
-        wad_file.write(&wfile.offset.to_le_bytes())?;
-        wad_file.write(&wfile.length.to_le_bytes())?;
+        wad_file
+            .write_all(&wfile.offset.to_le_bytes())
+            .and_then(|_| wad_file.write_all(&wfile.length.to_le_bytes()))
+            .map_err(WadError::io(&output_file))?;
     }
 
     for wfile in manifest.files {
-        let mut subfile = File::open(&wfile)?;
+        let mut subfile = File::open(&wfile).map_err(WadError::io(&wfile))?;
 
-        let mut limited = Read::by_ref(&mut subfile).take(1024);
-
-        let mut written = copy(&mut limited, &mut wad_file)?;
-
-        // copy full file
-        while written > 0 {
-            limited = Read::by_ref(&mut subfile).take(1024);
-            written = copy(&mut limited, &mut wad_file)?;
-        }
+        copy(&mut subfile, &mut wad_file).map_err(WadError::io(&output_file))?;
     }
 
     Ok(())
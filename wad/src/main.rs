@@ -1,7 +1,7 @@
-use std::{fs::File, path::PathBuf};
+use std::{env, path::PathBuf};
 
 use clap::{Parser, Subcommand};
-use wad::{parse_wad, rebuild_wad};
+use wad::{Manifest, Provenance, parse_wad, rebuild_wad};
 
 #[derive(Parser, Debug)]
 struct Args {
@@ -30,6 +30,8 @@ enum Command {
 
 fn main() -> anyhow::Result<()> {
     let args = Args::parse();
+    // files have to stay below the directory the tool runs in
+    let cwd = env::current_dir()?;
 
     match args.command {
         Command::Unpack {
@@ -37,19 +39,17 @@ fn main() -> anyhow::Result<()> {
             output_dir,
             json_out,
         } => {
-            let manifest = parse_wad(wad_file, output_dir)?;
+            // without a disc image the WAD is the source
+            let provenance = Provenance::of(&wad_file, concat!("wad ", env!("CARGO_PKG_VERSION")))?;
+            let manifest = parse_wad(cwd.join(wad_file), cwd.join(output_dir), provenance)?;
 
-            let json_out = File::create(json_out)?;
-
-            serde_json::to_writer_pretty(json_out, &manifest)?;
+            manifest.save(&cwd.join(json_out), &cwd)?;
         }
         Command::Pack {
             json_in,
             output_file,
         } => {
-            let json_in = File::open(json_in)?;
-
-            let manifest = serde_json::from_reader(json_in)?;
+            let manifest = Manifest::load(&cwd.join(json_in), &cwd)?;
 
             rebuild_wad(manifest, output_file)?;
         }
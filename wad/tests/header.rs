@@ -0,0 +1,61 @@
+use std::{fs, io::Cursor, path::Path};
+
+use wad::{Manifest, Provenance, WadError, read_wad, rebuild_wad};
+
+/// Two files, with entry `entry` of the table replaced
+fn wad_with_entry(entry: usize, offset: u32, length: u32) -> Vec<u8> {
+    let mut bytes = fixtures::wad::wad(&[vec![1; 16], vec![2; 16]]);
+    bytes[entry * 8..entry * 8 + 4].copy_from_slice(&offset.to_le_bytes());
+    bytes[entry * 8 + 4..entry * 8 + 8].copy_from_slice(&length.to_le_bytes());
+
+    bytes
+}
+
+fn read_error(bytes: Vec<u8>) -> String {
+    read_wad(&mut Cursor::new(bytes), Path::new("WAD.WAD"))
+        .unwrap_err()
+        .to_string()
+}
+
+#[test]
+fn rejects_files_past_the_end() {
+    let error = read_error(wad_with_entry(1, 2048 + 16, 17));
+
+    assert!(error.contains("header entry 1"), "{error}");
+    assert!(error.contains("past the end of the file"), "{error}");
+}
+
+#[test]
+fn rejects_files_wrapping_around() {
+    let error = read_error(wad_with_entry(1, 0xffff_fff0, 0x20));
+
+    assert!(error.contains("past 4 GiB"), "{error}");
+}
+
+#[test]
+fn rejects_files_out_of_order() {
+    assert!(read_error(wad_with_entry(1, 2048 + 8, 8)).contains("before the end of the previous"));
+    assert!(read_error(wad_with_entry(0, 16, 8)).contains("before the end of the previous"));
+}
+
+#[test]
+fn rebuild_rejects_files_past_4_gib() {
+    let dir = tempfile::tempdir().unwrap();
+    let huge = dir.path().join("0.bin");
+
+    // sparse, nothing is written
+    fs::File::create(&huge)
+        .unwrap()
+        .set_len(u32::MAX as u64 + 1)
+        .unwrap();
+
+    let manifest = Manifest {
+        schema_version: wad::SCHEMA_VERSION,
+        provenance: Provenance::default(),
+        files: vec![huge],
+    };
+
+    let result = rebuild_wad(manifest, dir.path().join("WAD.WAD"));
+
+    assert!(matches!(result, Err(WadError::TooLarge { .. })));
+}
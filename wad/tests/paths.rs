@@ -0,0 +1,81 @@
+use std::path::{Path, PathBuf};
+
+use wad::{relative_path, resolve_path};
+
+const ROOT: &str = "/project";
+const BASE: &str = "/project/extract/WAD";
+
+fn resolve(path: &str) -> anyhow::Result<PathBuf> {
+    resolve_path(Path::new(ROOT), Path::new(BASE), Path::new(path))
+}
+
+#[test]
+fn resolves_against_the_manifest_directory() {
+    assert_eq!(
+        resolve("0.bin").unwrap(),
+        Path::new("/project/extract/WAD/0.bin")
+    );
+    assert_eq!(
+        resolve("./levels/a.dat").unwrap(),
+        Path::new("/project/extract/WAD/levels/a.dat")
+    );
+}
+
+#[test]
+fn parent_directories_stay_inside_the_root() {
+    assert_eq!(
+        resolve("../SYSTEM.CNF").unwrap(),
+        Path::new("/project/extract/SYSTEM.CNF")
+    );
+    assert_eq!(
+        resolve("../../mods/a.bin").unwrap(),
+        Path::new("/project/mods/a.bin")
+    );
+    assert_eq!(
+        resolve("levels/../../../s2.json").unwrap(),
+        Path::new("/project/s2.json")
+    );
+}
+
+#[test]
+fn rejects_paths_escaping_the_root() {
+    assert!(resolve("../../../etc/passwd").is_err());
+    assert!(resolve("../../../project-other/a.bin").is_err());
+    assert!(resolve("../../../../../../../..").is_err());
+}
+
+#[test]
+fn rejects_absolute_paths() {
+    assert!(resolve("/project/extract/WAD/0.bin").is_err());
+    assert!(resolve("/etc/passwd").is_err());
+}
+
+#[test]
+fn relative_paths_round_trip() {
+    for path in [
+        "/project/extract/WAD/0.bin",
+        "/project/extract/WAD/levels/a.dat",
+        "/project/extract/SYSTEM.CNF",
+        "/project/mods/a.bin",
+    ] {
+        let relative = relative_path(Path::new(ROOT), Path::new(BASE), Path::new(path)).unwrap();
+
+        assert!(relative.is_relative());
+        assert_eq!(
+            resolve(relative.to_str().unwrap()).unwrap(),
+            Path::new(path)
+        );
+    }
+}
+
+#[test]
+fn relative_paths_must_be_inside_the_root() {
+    assert!(
+        relative_path(
+            Path::new(ROOT),
+            Path::new(BASE),
+            Path::new("/elsewhere/0.bin")
+        )
+        .is_err()
+    );
+}
@@ -0,0 +1,31 @@
+use std::{fs, io::Cursor, path::Path};
+
+use proptest::prelude::*;
+use wad::{Provenance, parse_wad, read_wad, rebuild_wad};
+
+proptest! {
+    #[test]
+    fn read_wad_returns_every_file(files in fixtures::wad::files()) {
+        let bytes = fixtures::wad::wad(&files);
+
+        let read = read_wad(&mut Cursor::new(bytes), Path::new("WAD.WAD")).unwrap();
+
+        prop_assert_eq!(read, files);
+    }
+
+    #[test]
+    fn parse_then_rebuild_is_identical(files in fixtures::wad::files()) {
+        let dir = tempfile::tempdir().unwrap();
+        let wad_file = dir.path().join("WAD.WAD");
+        let rebuilt = dir.path().join("rebuilt.WAD");
+
+        let bytes = fixtures::wad::wad(&files);
+        fs::write(&wad_file, &bytes).unwrap();
+
+        let provenance = Provenance::of(&wad_file, "test").unwrap();
+        let manifest = parse_wad(wad_file, dir.path().join("wad"), provenance).unwrap();
+        rebuild_wad(manifest, rebuilt.clone()).unwrap();
+
+        prop_assert_eq!(fs::read(rebuilt).unwrap(), bytes);
+    }
+}